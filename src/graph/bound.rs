@@ -0,0 +1,76 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use super::Graph;
+
+impl Graph {
+    /// Turns needed by a lone ant to go from start to end
+    pub fn shortest_duration(&self) -> Option<usize> {
+        let mut durations = vec![usize::MAX; self.nodes.len()];
+        durations[usize::from(self.start)] = 0;
+        let mut queue = BinaryHeap::from([Reverse((0, self.start))]);
+        while let Some(Reverse((duration, id))) = queue.pop() {
+            if id == self.end {
+                return Some(duration);
+            }
+            if duration > durations[usize::from(id)] {
+                continue;
+            }
+            for link in &self[id].links {
+                let next = duration + link.length;
+                if next < durations[usize::from(link.to)] {
+                    durations[usize::from(link.to)] = next;
+                    queue.push(Reverse((next, link.to)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Turns no solution can beat, `None` when ants can not reach the end
+    /// The first ant arrives after the shortest duration at best, & every
+    /// turn at most as many ants as the min cut holds get through it
+    pub fn turn_lower_bound(&self) -> Option<usize> {
+        self.turn_lower_bound_for(self.ant_count)
+    }
+
+    /// Same as [`Graph::turn_lower_bound`] for `ant_count` ants
+    pub(crate) fn turn_lower_bound_for(&self, ant_count: usize) -> Option<usize> {
+        if ant_count == 0 || self.start == self.end {
+            return Some(0);
+        }
+        let duration = self.shortest_duration()?;
+        let cut = self.max_disjoint_paths();
+        // Saturated for ant counts a long path can not fit in, still a bound
+        Some(duration.saturating_add(ant_count.div_ceil(cut) - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Graph;
+
+    #[test]
+    fn lower_bound_of_handmade_maps() {
+        let subject: Graph = include_str!("../../maps/handmade/subject_map").parse().unwrap();
+        let twin: Graph = "4\n##start\ns 0 0\na 0 0\nb 0 0\n##end\ne 0 0\ns-a\na-e\ns-b\nb-e"
+            .parse()
+            .unwrap();
+
+        assert_eq!(subject.shortest_duration(), Some(3));
+        assert_eq!(subject.turn_lower_bound(), Some(3 + subject.ant_count().div_ceil(2) - 1));
+        assert!(subject.turn_lower_bound() < Some(subject.solve().unwrap().turn_count()));
+        assert!(subject.solve().unwrap().is_optimal());
+        assert_eq!(twin.turn_lower_bound(), Some(3));
+        assert!(twin.solve().unwrap().is_optimal());
+    }
+
+    #[test]
+    fn lower_bound_of_the_most_ants() {
+        let map = format!("{}\n##start\ns 0 0\na 0 0\n##end\ne 0 0\ns-a\na-e", usize::MAX);
+        let graph: Graph = map.parse().unwrap();
+
+        assert_eq!(graph.turn_lower_bound(), Some(usize::MAX));
+        assert_eq!(graph.turn_lower_bound_for(usize::MAX - 1), Some(usize::MAX));
+        assert_eq!(graph.solve().unwrap().lower_bound(), Some(usize::MAX));
+    }
+}
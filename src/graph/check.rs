@@ -0,0 +1,187 @@
+use core::fmt;
+use std::{collections::HashMap, error::Error};
+
+use super::{
+    schedule::{stays, Reservations, Stay},
+    Arrival, Graph, NodeId,
+};
+
+/// Why a transcript is not a valid solution of a map
+#[derive(Debug)]
+pub enum CheckError {
+    InvalidMove(String),
+    UnknownAnt(usize),
+    UnknownRoom(String),
+    /// Ant moving again during a turn
+    MovedTwice { turn: usize, ant: usize },
+    /// Ant moving after reaching its end room
+    AlreadyArrived { turn: usize, ant: usize },
+    NotLinked { turn: usize, ant: usize, room: String },
+    /// Ant getting out of a tunnel before having gone all the way through
+    TooFast { turn: usize, ant: usize, room: String },
+    /// Ant in a closed room or tunnel
+    Closed { turn: usize, ant: usize, place: String },
+    /// Room holding too many ants, or ants crossing in a tunnel
+    Blocked { turn: usize, ant: usize, place: String },
+    NotArrived(usize),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckError::InvalidMove(step) => write!(f, "Invalid move {step}"),
+            CheckError::UnknownAnt(ant) => write!(f, "Unknown ant L{ant}"),
+            CheckError::UnknownRoom(room) => write!(f, "Unknown room {room}"),
+            CheckError::MovedTwice { turn, ant } => {
+                write!(f, "Turn {turn}: L{ant} moves twice")
+            }
+            CheckError::AlreadyArrived { turn, ant } => {
+                write!(f, "Turn {turn}: L{ant} already arrived")
+            }
+            CheckError::NotLinked { turn, ant, room } => {
+                write!(f, "Turn {turn}: L{ant} can not reach {room}")
+            }
+            CheckError::TooFast { turn, ant, room } => {
+                write!(f, "Turn {turn}: L{ant} reaches {room} too early")
+            }
+            CheckError::Closed { turn, ant, place } => {
+                write!(f, "Turn {turn}: L{ant} is in {place} while it is closed")
+            }
+            CheckError::Blocked { turn, ant, place } => {
+                write!(f, "Turn {turn}: L{ant} does not fit in {place}")
+            }
+            CheckError::NotArrived(ant) => write!(f, "L{ant} never arrives"),
+        }
+    }
+}
+
+impl Error for CheckError {}
+
+impl Graph {
+    /// Start & end rooms of every ant, main colony first
+    fn ant_terminals(&self) -> Vec<(NodeId, NodeId)> {
+        let mut terminals = vec![(self.start, self.end); self.ant_count];
+        for colony in &self.colonies {
+            terminals.extend(std::iter::repeat_n((colony.start, colony.end), colony.ant_count));
+        }
+        terminals
+    }
+
+    /// Whether an ant in `room` is done, instant tunnels join end rooms
+    fn reaches(&self, room: NodeId, end: NodeId) -> bool {
+        room == end || self.link_length(room, end) == Some(0)
+    }
+
+    fn place(&self, stay: Stay) -> String {
+        match stay {
+            Stay::Room(room, _) => format!("room {}", self[room].name.as_ref()),
+            Stay::Tunnel(from, to, _) | Stay::Entry(from, to, _) => {
+                format!("tunnel {}-{}", self[from].name.as_ref(), self[to].name.as_ref())
+            }
+        }
+    }
+
+    /// Replay a printed solution, one line per turn, & return its number of
+    /// turns. As moves out of a room are not printed, an ant is assumed to
+    /// stay in a room until the last turn it can leave it
+    pub fn check(&self, transcript: &str) -> Result<usize, CheckError> {
+        let terminals = self.ant_terminals();
+        let ids: HashMap<&str, NodeId> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (node.name.as_ref(), NodeId::from(id)))
+            .filter(|&(_, id)| self.is_room(id))
+            .collect();
+        let mut routes: Vec<Vec<Arrival>> = vec![Vec::new(); terminals.len()];
+
+        let mut turn_count = 0;
+        for (turn, line) in transcript.lines().enumerate() {
+            let turn = turn + 1;
+            turn_count = turn;
+            for step in line.split_whitespace() {
+                let (ant, room) = step
+                    .strip_prefix('L')
+                    .and_then(|step| step.split_once('-'))
+                    .and_then(|(ant, room)| Some((ant.parse::<usize>().ok()?, room)))
+                    .ok_or_else(|| CheckError::InvalidMove(step.to_owned()))?;
+                let &(start, end) = ant
+                    .checked_sub(1)
+                    .and_then(|index| terminals.get(index))
+                    .ok_or(CheckError::UnknownAnt(ant))?;
+                let &to = ids.get(room).ok_or_else(|| CheckError::UnknownRoom(room.to_owned()))?;
+                let route = &mut routes[ant - 1];
+
+                let &(arrival, from) = route.last().unwrap_or(&(0, start));
+                if arrival == turn {
+                    return Err(CheckError::MovedTwice { turn, ant });
+                }
+                if !route.is_empty() && self.reaches(from, end) {
+                    return Err(CheckError::AlreadyArrived { turn, ant });
+                }
+                let room = room.to_owned();
+                let length = self
+                    .hop_length(from, to)
+                    .ok_or_else(|| CheckError::NotLinked { turn, ant, room: room.clone() })?;
+                if turn - arrival < length {
+                    return Err(CheckError::TooFast { turn, ant, room });
+                }
+                route.push((turn, to));
+            }
+        }
+
+        let mut reservations = Reservations::default();
+        for (index, (route, &(start, end))) in routes.iter().zip(&terminals).enumerate() {
+            let ant = index + 1;
+            reservations.set_terminals(self.terminal_rooms(start, end));
+            let &(_, room) = route.last().unwrap_or(&(0, start));
+            if !self.reaches(room, end) {
+                return Err(CheckError::NotArrived(ant));
+            }
+            for stay in stays(self, start, route) {
+                let turn = stay.turn();
+                if !stay.is_open(self) {
+                    return Err(CheckError::Closed { turn, ant, place: self.place(stay) });
+                }
+                if !reservations.allows(self, stay) {
+                    return Err(CheckError::Blocked { turn, ant, place: self.place(stay) });
+                }
+            }
+            reservations.reserve(self, start, route);
+        }
+        Ok(turn_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CheckError;
+    use crate::{Graph, ParseOptions};
+
+    const MAP: &str = "2\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+        s-a\na-e\ns-b\nb-c\nc-e";
+
+    #[test]
+    fn solutions_pass_the_check() {
+        let graph: Graph = include_str!("../../maps/handmade/subject_map").parse().unwrap();
+        let solution = graph.solve().unwrap();
+        let mut output = Vec::new();
+        solution.write_to(&graph, &mut output).unwrap();
+
+        let turns = graph.check(std::str::from_utf8(&output).unwrap()).unwrap();
+        assert_eq!(turns, solution.to_schedule(&graph).turn_count());
+    }
+
+    #[test]
+    fn closed_room_is_rejected() {
+        let transcript = "L1-a L2-b\nL1-e L2-c\nL2-e";
+        let open: Graph = MAP.parse().unwrap();
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let closed = Graph::parse_with(&format!("{MAP}\n##closed a 1 2"), &options).unwrap();
+
+        assert_eq!(open.check(transcript).unwrap(), 3);
+        assert!(matches!(closed.check(transcript), Err(CheckError::Closed { turn: 1, .. })));
+        assert!(matches!(open.check("L1-a\nL1-c"), Err(CheckError::NotLinked { turn: 2, .. })));
+        assert!(matches!(open.check("L1-a L2-a\nL1-e L2-e"), Err(CheckError::Blocked { .. })));
+    }
+}
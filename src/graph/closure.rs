@@ -0,0 +1,207 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    ops::RangeInclusive,
+};
+
+use super::{
+    schedule::{Reservations, Stay},
+    Arrival, Graph, NodeId, Schedule, SolveOptions,
+};
+
+/// Part of the map that can be closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Element {
+    Room(NodeId),
+    /// A tunnel, closed in both directions
+    Link(NodeId, NodeId),
+}
+
+/// An element no ant may use during some turns, declared with
+/// `##closed ROOM FIRST LAST` or `##closed A-B FIRST LAST`
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub element: Element,
+    pub turns: RangeInclusive<usize>,
+}
+
+impl Graph {
+    pub fn closures(&self) -> &[Closure] {
+        &self.closures
+    }
+
+    /// Whether no ant may be in `room` at the end of `turn`
+    pub fn is_room_closed(&self, room: NodeId, turn: usize) -> bool {
+        self.closures
+            .iter()
+            .any(|closure| closure.element == Element::Room(room) && closure.turns.contains(&turn))
+    }
+
+    /// Whether no ant may go through the tunnel between `a` & `b` during `turn`
+    pub fn is_link_closed(&self, a: NodeId, b: NodeId, turn: usize) -> bool {
+        self.closures.iter().any(|closure| {
+            let linked = closure.element == Element::Link(a, b)
+                || closure.element == Element::Link(b, a);
+            linked && closure.turns.contains(&turn)
+        })
+    }
+
+    /// Parse the value of a `##closed` tag, once every room & link is known
+    pub(super) fn parse_closure(&self, s: &str) -> Option<Closure> {
+        let mut fields = s.split(' ');
+        let element = fields.next()?;
+        let first: usize = fields.next()?.parse().ok()?;
+        let last: usize = fields.next()?.parse().ok()?;
+        if fields.next().is_some() || first == 0 || first > last {
+            return None;
+        }
+        let element = match element.split_once('-') {
+            Some((a, b)) => {
+                let (a, b) = (self.find(a)?, self.find(b)?);
+                self.link_length(a, b).or(self.link_length(b, a))?;
+                Element::Link(a, b)
+            }
+            None => Element::Room(self.find(element)?),
+        };
+        Some(Closure { element, turns: first..=last })
+    }
+
+    /// Route every ant, of every colony, around the closed rooms & tunnels
+    /// Ants are planned one after the other over the time-expanded map
+    /// (a room at a given turn), each one taking the earliest route left by
+    /// the ants before it. Ants may wait in a room for it to reopen its way
+    /// `None` once past the deadline of `options` or cancelled, as the
+    /// schedule is only valid once every ant is planned
+    pub fn solve_with_closures(&self, options: &SolveOptions) -> Option<Schedule> {
+        let mut colonies = vec![(self.start, self.end, self.ant_count)];
+        colonies.extend(
+            self.colonies.iter().map(|colony| (colony.start, colony.end, colony.ant_count)),
+        );
+        let mut reservations = Reservations::default();
+
+        let longest_link = self
+            .nodes
+            .iter()
+            .flat_map(|node| &node.links)
+            .map(|link| link.length)
+            .max()
+            .unwrap_or(0);
+        let last_closure = self.closures.iter().map(|closure| *closure.turns.end()).max();
+
+        let mut schedule = Schedule::default();
+        for (start, end, ant_count) in colonies {
+            reservations.set_terminals(self.terminal_rooms(start, end));
+            for _ in 0..ant_count {
+                if options.is_late() || options.is_cancelled() {
+                    return None;
+                }
+                // Once nothing is closed nor reserved anymore, any simple
+                // path gets the ant to the end
+                let horizon = reservations.last_turn().max(last_closure.unwrap_or(0))
+                    + self.nodes.len() * longest_link.max(1);
+                let route = self.earliest_route(&reservations, start, end, horizon)?;
+                reservations.reserve(self, start, &route);
+                schedule.push(route);
+            }
+        }
+        Some(schedule)
+    }
+
+    /// Earliest arrival in `end` of an ant leaving `start`, through open
+    /// rooms & tunnels left free by the reservations (Dijkstra over turns)
+    fn earliest_route(
+        &self,
+        reservations: &Reservations,
+        start: NodeId,
+        end: NodeId,
+        horizon: usize,
+    ) -> Option<Vec<Arrival>> {
+        let free = |stay: Stay| stay.is_open(self) && reservations.allows(self, stay);
+        // Room the ant was in before reaching a room at a turn
+        let mut parents: HashMap<(NodeId, usize), NodeId> = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, start, start))]);
+
+        while let Some(Reverse((turn, node, parent))) = queue.pop() {
+            if parents.contains_key(&(node, turn)) {
+                continue;
+            }
+            parents.insert((node, turn), parent);
+            if node == end {
+                return Some(self.arrivals(&parents, start, (node, turn)));
+            }
+            if turn >= horizon {
+                continue;
+            }
+            if node == start || free(Stay::Room(node, turn + 1)) {
+                queue.push(Reverse((turn + 1, node, node)));
+            }
+            for link in &self[node].links {
+                // A longer duplicate tunnel is no better than waiting
+                if self.link_length(node, link.to) != Some(link.length) {
+                    continue;
+                }
+                let arrival = turn + link.length;
+                let open_tunnel = free(Stay::Entry(node, link.to, turn + 1))
+                    && (turn + 1..=arrival).all(|turn| free(Stay::Tunnel(node, link.to, turn)));
+                if open_tunnel && free(Stay::Room(link.to, arrival)) {
+                    queue.push(Reverse((arrival, link.to, node)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Walk back the parents of `last` to list the printed moves
+    fn arrivals(
+        &self,
+        parents: &HashMap<(NodeId, usize), NodeId>,
+        start: NodeId,
+        last: (NodeId, usize),
+    ) -> Vec<Arrival> {
+        let mut route = Vec::new();
+        let (mut node, mut turn) = last;
+        while (node, turn) != (start, 0) {
+            let parent = parents[&(node, turn)];
+            if parent == node {
+                turn -= 1;
+                continue;
+            }
+            let length = self.link_length(parent, node).expect("routes follow links");
+            if length != 0 {
+                route.push((turn, node));
+            }
+            turn -= length;
+            node = parent;
+        }
+        route.reverse();
+        route
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::{Graph, ParseOptions, SolveOptions};
+
+    #[test]
+    fn ants_go_around_a_closed_room() {
+        let map = "2\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a\na-e\ns-b\nb-c\nc-e\n##closed a 1 2";
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let graph = Graph::parse_with(map, &options).unwrap();
+        let schedule = graph.solve_with_closures(&SolveOptions::default()).unwrap();
+        let a = graph.find("a").unwrap();
+
+        assert_eq!(graph.closures().len(), 1);
+        assert_eq!(schedule.turn_count(), 4);
+        let mut output = Vec::new();
+        schedule.write_to(&graph, &mut output).unwrap();
+        assert_eq!(graph.check(std::str::from_utf8(&output).unwrap()).unwrap(), 4);
+        for route in schedule.routes() {
+            assert!(route.iter().all(|&(turn, node)| node != a || turn > 2));
+        }
+        let late = SolveOptions { deadline: Some(Instant::now()), ..SolveOptions::default() };
+        assert!(graph.solve_with_closures(&late).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+use super::{
+    schedule::{route, Reservations},
+    Graph, NodeId, Schedule, SolveOptions,
+};
+
+/// Ants sharing the map with the main colony, going from their own start
+/// room to their own end room
+#[derive(Debug, Clone)]
+pub struct Colony {
+    pub name: String,
+    pub start: NodeId,
+    pub end: NodeId,
+    pub ant_count: usize,
+}
+
+impl Graph {
+    /// Colonies other than the main one, declared with `##colony`
+    pub fn colonies(&self) -> &[Colony] {
+        &self.colonies
+    }
+
+    /// Rooms the ants going from `start` to `end` can be in any number,
+    /// the rooms joined by the virtual start & end rooms of the main colony
+    /// included
+    pub(crate) fn terminal_rooms(&self, start: NodeId, end: NodeId) -> Vec<NodeId> {
+        let mut rooms = vec![start, end];
+        if start == self.start {
+            rooms.extend(&self.starts);
+        }
+        if end == self.end {
+            rooms.extend(&self.ends);
+        }
+        rooms
+    }
+
+    /// The map as seen by a single colony
+    fn colony_view(&self, colony: &Colony) -> Graph {
+        Graph {
+            nodes: self.nodes.clone(),
+            start: colony.start,
+            end: colony.end,
+            starts: vec![colony.start],
+            ends: vec![colony.end],
+            ant_count: colony.ant_count,
+            colonies: Vec::new(),
+            closures: self.closures.clone(),
+            routing: self.routing,
+        }
+    }
+
+    /// Route every colony through the map in one combined schedule
+    /// Colonies are planned one after the other: each one follows its own
+    /// best paths, its ants waiting in start until no room on their path
+    /// would be overfilled by the ants of the colonies planned before
+    /// Each colony is solved within the limits of `options`, see
+    /// [`Graph::solve_with`]
+    pub fn solve_colonies(&self, options: &SolveOptions) -> Option<Schedule> {
+        let main = Colony {
+            name: String::new(),
+            start: self.start,
+            end: self.end,
+            ant_count: self.ant_count,
+        };
+        let colonies: Vec<_> = std::iter::once(&main).chain(&self.colonies).collect();
+        let mut reservations = Reservations::default();
+
+        let mut schedule = Schedule::default();
+        for colony in colonies {
+            reservations.set_terminals(self.terminal_rooms(colony.start, colony.end));
+            let view = self.colony_view(colony);
+            let solution = view.solve_with(options)?;
+            for (departure, path) in solution.departures() {
+                let path = &solution.paths()[path];
+                let route = (departure..)
+                    .map(|departure| route(&view, path, departure))
+                    .find(|route| reservations.fits(&view, view.start, route))
+                    .expect("reservations end at some point");
+                reservations.reserve(&view, view.start, &route);
+                schedule.push(route);
+            }
+        }
+        Some(schedule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Graph, ParseOptions, SolveOptions};
+
+    #[test]
+    fn colonies_share_the_crossing() {
+        let map = "2\n##colony blue 2\n##start\na 0 0\n##end\nb 0 0\n\
+            ##start blue\nc 0 0\n##end blue\nd 0 0\nm 0 0\na-m\nm-b\nc-m\nm-d";
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let graph = Graph::parse_with(map, &options).unwrap();
+        let schedule = graph.solve_colonies(&SolveOptions::default()).unwrap();
+        let m = graph.find("m").unwrap();
+
+        assert_eq!(graph.colonies().len(), 1);
+        assert_eq!(schedule.routes().len(), 4);
+        assert_eq!(schedule.turn_count(), 5);
+        for moves in schedule.turns() {
+            assert!(moves.iter().filter(|step| step.node == m).count() <= 1);
+        }
+    }
+
+    #[test]
+    fn terminals_are_rooms_for_other_colonies() {
+        let map = "1\n##colony blue 1\n##colony green 1\n##start\na 0 0\n##end\nb 0 0\n\
+            ##start blue\nc 0 0\n##end blue\nd 0 0\n##start green\nf 0 0\n##end green\ng 0 0\n\
+            a-b\nc-a\na-d\nf-a\na-g";
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let graph = Graph::parse_with(map, &options).unwrap();
+        let schedule = graph.solve_colonies(&SolveOptions::default()).unwrap();
+        let a = graph.find("a").unwrap();
+
+        assert_eq!(schedule.turn_count(), 3);
+        for moves in schedule.turns() {
+            assert!(moves.iter().filter(|step| step.node == a).count() <= 1);
+        }
+    }
+}
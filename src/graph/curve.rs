@@ -0,0 +1,229 @@
+use std::io;
+
+use super::{flow::MinCostFlow, Graph};
+use crate::path::Path;
+
+/// Where the curve bends: from `turns` turns on, every extra turn brings
+/// `paths` more ants to the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub turns: usize,
+    /// Ants reaching the end within `turns` turns
+    pub ants: usize,
+    pub paths: usize,
+}
+
+/// Fewest turns as a function of the ant count, & the other way around
+/// With `C(k)` the least total duration of `k` paths, ants leave along each
+/// path every turn so `T` turns bring `max k(T + 1) - C(k)` ants to the end
+#[derive(Debug, Clone)]
+pub struct TurnCurve {
+    /// `C(k)` for `k` from 1 to the maximum flow
+    costs: Vec<usize>,
+    /// Whether start is the end, so any number of ants takes no turn
+    instant: bool,
+}
+
+impl TurnCurve {
+    /// Curve of the successive paths of `flow` as long as adding one costs
+    /// less than `max_cost`, enough to tell turn counts below `max_cost`,
+    /// with the paths of the last flow kept in `paths`
+    pub(crate) fn from_flow(
+        mut flow: MinCostFlow,
+        max_cost: usize,
+        mut paths: Option<&mut Vec<Path>>,
+    ) -> Self {
+        let mut costs = Vec::new();
+        let mut total = 0;
+        while flow.augment() {
+            let cost = flow.last_path_cost() as usize;
+            if cost >= max_cost {
+                break;
+            }
+            total += cost;
+            costs.push(total);
+            if let Some(paths) = paths.as_deref_mut() {
+                *paths = flow.paths();
+            }
+        }
+        Self { costs, instant: false }
+    }
+
+    /// Curve of the successive flows of `snapshots`, one more path each
+    pub(crate) fn from_snapshots(snapshots: &[MinCostFlow]) -> Self {
+        let mut costs = Vec::new();
+        let mut total = 0;
+        for flow in snapshots.iter().skip(1) {
+            total += flow.last_path_cost() as usize;
+            costs.push(total);
+        }
+        Self { costs, instant: false }
+    }
+
+    /// `C(k)` for `k` from 1 on
+    pub(crate) fn costs(&self) -> &[usize] {
+        &self.costs
+    }
+
+    /// Fewest turns for `ant_count` ants along paths of total durations
+    /// `costs` then the next paths of `flow`, added as long as they can
+    /// lower the turns, with the flow before each of them in `snapshots`
+    pub(crate) fn min_turns_from(
+        costs: &[usize],
+        mut flow: MinCostFlow,
+        ant_count: usize,
+        mut snapshots: Option<&mut Vec<MinCostFlow>>,
+    ) -> Option<usize> {
+        let mut curve = Self { costs: costs.to_vec(), instant: false };
+        let mut best = curve.min_turns(ant_count);
+        loop {
+            if let Some(snapshots) = snapshots.as_deref_mut() {
+                snapshots.push(flow.clone());
+            }
+            if !flow.augment() {
+                return best;
+            }
+            // A path costing as many turns as the best found can not help
+            let cost = flow.last_path_cost() as usize;
+            if best.is_some_and(|best| cost >= best) {
+                return best;
+            }
+            curve.costs.push(curve.costs.last().copied().unwrap_or(0) + cost);
+            best = curve.min_turns(ant_count);
+        }
+    }
+
+    /// Ants reaching the end within `turns` turns with `index + 1` paths,
+    /// saturated to `usize::MAX`
+    fn ants_along(&self, index: usize, turns: usize) -> usize {
+        let paths = index as u128 + 1;
+        let ants = (paths * (turns as u128 + 1)).saturating_sub(self.costs[index] as u128);
+        usize::try_from(ants).unwrap_or(usize::MAX)
+    }
+
+    /// Bends of the curve by increasing turns, none when the end can not be
+    /// reached
+    pub fn breakpoints(&self) -> Vec<Breakpoint> {
+        let mut breakpoints: Vec<Breakpoint> = Vec::new();
+        let mut previous = 0;
+        for (index, &cost) in self.costs.iter().enumerate() {
+            // Path costs of successive shortest paths never decrease: one
+            // more path pays off once the turns exceed its cost
+            let turns = (cost - previous).saturating_sub(1);
+            previous = cost;
+            let breakpoint = Breakpoint {
+                turns,
+                ants: self.ants_along(index, turns),
+                paths: index + 1,
+            };
+            match breakpoints.last_mut() {
+                Some(last) if last.turns == turns => *last = breakpoint,
+                _ => breakpoints.push(breakpoint),
+            }
+        }
+        breakpoints
+    }
+
+    /// Most ants reaching the end within `turns` turns
+    pub fn max_ants(&self, turns: usize) -> usize {
+        if self.instant {
+            return usize::MAX;
+        }
+        (0..self.costs.len()).map(|index| self.ants_along(index, turns)).max().unwrap_or(0)
+    }
+
+    /// Fewest turns for `ant_count` ants, `None` when they can not reach
+    /// the end
+    pub fn min_turns(&self, ant_count: usize) -> Option<usize> {
+        if ant_count == 0 || self.instant {
+            return Some(0);
+        }
+        self.costs
+            .iter()
+            .enumerate()
+            .map(|(index, &cost)| {
+                let turns = (ant_count as u128 + cost as u128).div_ceil(index as u128 + 1) - 1;
+                usize::try_from(turns).unwrap_or(usize::MAX)
+            })
+            .min()
+    }
+
+    pub fn write_human(&self, mut output: impl io::Write) -> io::Result<()> {
+        if self.instant {
+            return writeln!(output, "Any number of ants in 0 turns");
+        }
+        if self.costs.is_empty() {
+            return writeln!(output, "No ant can reach the end");
+        }
+        for Breakpoint { turns, ants, paths } in self.breakpoints() {
+            writeln!(output, "{turns} turns: {ants} ants, then {paths} more per turn")?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, mut output: impl io::Write) -> io::Result<()> {
+        let breakpoints: Vec<_> = self
+            .breakpoints()
+            .iter()
+            .map(|Breakpoint { turns, ants, paths }| {
+                format!("{{\"turns\": {turns}, \"ants\": {ants}, \"paths\": {paths}}}")
+            })
+            .collect();
+        writeln!(output, "{{")?;
+        writeln!(output, "  \"instant\": {},", self.instant)?;
+        writeln!(output, "  \"breakpoints\": [{}]", breakpoints.join(", "))?;
+        writeln!(output, "}}")
+    }
+}
+
+impl Graph {
+    /// Fewest turns for every ant count, from the min-cost flow of every size
+    pub fn turn_curve(&self) -> TurnCurve {
+        if self.start == self.end {
+            return TurnCurve { costs: Vec::new(), instant: true };
+        }
+        TurnCurve::from_flow(MinCostFlow::new(self), usize::MAX, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Breakpoint;
+    use crate::Graph;
+
+    #[test]
+    fn curve_matches_the_solver() {
+        let maps = [
+            include_str!("../../maps/handmade/subject_map"),
+            include_str!("../../maps/handmade/bottleneck"),
+            include_str!("../../maps/handmade/three_route"),
+            include_str!("../../maps/handmade/duration"),
+        ];
+        for map in maps {
+            let graph: Graph = map.parse().unwrap();
+            let curve = graph.turn_curve();
+            let turns = graph.solve().unwrap().turn_count();
+
+            assert_eq!(curve.min_turns(graph.ant_count()), Some(turns));
+            assert!(curve.max_ants(turns) >= graph.ant_count());
+            assert!(curve.max_ants(turns - 1) < graph.ant_count());
+        }
+    }
+
+    #[test]
+    fn breakpoints_of_twin_paths() {
+        let graph: Graph = "1\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a\na-e\ns-b\nb-c\nc-e"
+            .parse()
+            .unwrap();
+        let curve = graph.turn_curve();
+
+        assert_eq!(curve.breakpoints(), [
+            Breakpoint { turns: 1, ants: 0, paths: 1 },
+            Breakpoint { turns: 2, ants: 1, paths: 2 },
+        ]);
+        assert_eq!(curve.max_ants(4), 5);
+        assert_eq!(curve.min_turns(5), Some(4));
+        assert_eq!(curve.min_turns(usize::MAX), Some(usize::MAX / 2 + 2));
+    }
+}
@@ -0,0 +1,399 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, VecDeque}};
+
+use super::{Element, Graph, Link, NodeId};
+use crate::path::Path;
+
+const UNBOUNDED: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct Arc {
+    to: usize,
+    residual: usize,
+    /// Length of the tunnel, negated on reverse arcs
+    cost: i64,
+}
+
+/// Residual network over the node-split graph
+/// Every room is an `in` vertex (`2 * id`) linked to an `out` vertex
+/// (`2 * id + 1`), each link goes from an `out` vertex to an `in` vertex
+#[derive(Debug, Clone)]
+pub(crate) struct FlowNetwork {
+    arcs: Vec<Arc>,
+    heads: Vec<Vec<usize>>,
+    source: usize,
+    sink: usize,
+}
+
+impl FlowNetwork {
+    pub fn new(graph: &Graph) -> Self {
+        Self::with_costs(graph, |_, link| link.length as i64)
+    }
+
+    /// Network where going through a tunnel costs `cost(from, link)`
+    pub fn with_costs(graph: &Graph, mut cost: impl FnMut(NodeId, Link) -> i64) -> Self {
+        let mut network = Self {
+            arcs: Vec::new(),
+            heads: vec![Vec::new(); 2 * graph.nodes().len()],
+            source: out_vertex(graph.start()),
+            sink: in_vertex(graph.end()),
+        };
+        for (id, node) in graph.nodes().iter().enumerate() {
+            let id = NodeId::from(id);
+            if id != graph.start() && id != graph.end() {
+                network.add_arc(in_vertex(id), out_vertex(id), node.capacity);
+            }
+            for &link in &node.links {
+                let capacity = link_capacity(graph, id, link);
+                network.add_costed_arc(out_vertex(id), in_vertex(link.to), capacity, cost(id, link));
+            }
+        }
+        network
+    }
+
+    /// Network without arcs, vertices are added with [`Self::add_vertices`]
+    pub fn empty(source: usize, sink: usize) -> Self {
+        Self {
+            arcs: Vec::new(),
+            heads: vec![Vec::new(); source.max(sink) + 1],
+            source,
+            sink,
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.heads.len()
+    }
+
+    pub fn add_vertices(&mut self, count: usize) {
+        self.heads.resize(self.heads.len() + count, Vec::new());
+    }
+
+    pub fn add_arc(&mut self, from: usize, to: usize, capacity: usize) {
+        self.add_costed_arc(from, to, capacity, 0);
+    }
+
+    /// Add a tunnel leaving `from`, as [`Self::new`] does for the map ones
+    pub fn add_link(&mut self, graph: &Graph, from: NodeId, link: Link) {
+        let capacity = link_capacity(graph, from, link);
+        self.add_costed_arc(out_vertex(from), in_vertex(link.to), capacity, link.length as i64);
+    }
+
+    fn add_costed_arc(&mut self, from: usize, to: usize, capacity: usize, cost: i64) {
+        self.heads[from].push(self.arcs.len());
+        self.arcs.push(Arc { to, residual: capacity, cost });
+        self.heads[to].push(self.arcs.len());
+        self.arcs.push(Arc { to: from, residual: 0, cost: -cost });
+    }
+
+    /// Push one unit of flow along a shortest augmenting path
+    fn augment(&mut self) -> bool {
+        let mut parent_arc = vec![None; self.heads.len()];
+        let mut queue = VecDeque::from([self.source]);
+        while let Some(vertex) = queue.pop_front() {
+            if vertex == self.sink {
+                break;
+            }
+            for &arc in &self.heads[vertex] {
+                let Arc { to, residual, .. } = self.arcs[arc];
+                if residual != 0 && to != self.source && parent_arc[to].is_none() {
+                    parent_arc[to] = Some(arc);
+                    queue.push_back(to);
+                }
+            }
+        }
+
+        if parent_arc[self.sink].is_none() {
+            return false;
+        }
+        let mut vertex = self.sink;
+        while let Some(arc) = parent_arc[vertex] {
+            self.arcs[arc].residual -= 1;
+            self.arcs[arc ^ 1].residual += 1;
+            vertex = self.arcs[arc ^ 1].to;
+        }
+        true
+    }
+
+    /// Saturate the network, stopping once `limit` units are flowing
+    pub fn max_flow(&mut self, limit: usize) -> usize {
+        let mut flow = 0;
+        while flow < limit && self.augment() {
+            flow += 1;
+        }
+        flow
+    }
+
+    /// Paths followed by the units of flow
+    pub fn paths(&self) -> Vec<Path> {
+        let Self { arcs, heads, source, sink } = self;
+        // Flow through each forward arc, read on its reverse arc
+        let mut flow: Vec<_> = arcs.chunks(2).map(|pair| pair[1].residual).collect();
+        let mut paths = Vec::new();
+        while let Some(first) = heads[*source].iter().find(|&&arc| arc % 2 == 0 && flow[arc / 2] != 0) {
+            let mut path = Vec::new();
+            let mut arc = *first;
+            loop {
+                flow[arc / 2] -= 1;
+                let to = arcs[arc].to;
+                // Links end on `in` vertices, rooms on `out` vertices
+                if to % 2 == 0 {
+                    path.push(NodeId::from(to / 2));
+                }
+                if to == *sink {
+                    break;
+                }
+                arc = *heads[to]
+                    .iter()
+                    .find(|&&arc| arc % 2 == 0 && flow[arc / 2] != 0)
+                    .expect("flow is conserved");
+            }
+            paths.push(Path::from_iter(path));
+        }
+        paths
+    }
+
+    /// Saturate the arcs from `from` to `to`
+    fn close_arcs(&mut self, from: usize, to: usize) {
+        for &arc in &self.heads[from] {
+            if arc % 2 == 0 && self.arcs[arc].to == to {
+                self.arcs[arc].residual = 0;
+            }
+        }
+    }
+
+    /// Vertices reachable from the source in the residual network
+    fn residual_reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.heads.len()];
+        reachable[self.source] = true;
+        let mut queue = VecDeque::from([self.source]);
+        while let Some(vertex) = queue.pop_front() {
+            for &arc in &self.heads[vertex] {
+                let Arc { to, residual, .. } = self.arcs[arc];
+                if residual != 0 && !reachable[to] {
+                    reachable[to] = true;
+                    queue.push_back(to);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+/// Ants that can go through a tunnel at once
+/// A tunnel between start & end rooms is the only one that is not already
+/// limited by the rooms it touches, instant tunnels are the virtual ones
+/// joining several start or end rooms
+pub(crate) fn link_capacity(graph: &Graph, from: NodeId, link: Link) -> usize {
+    let unbounded = |id: NodeId| {
+        id == graph.start() || id == graph.end() || graph[id].capacity == UNBOUNDED
+    };
+    if link.length != 0 && unbounded(from) && unbounded(link.to) {
+        1
+    } else {
+        UNBOUNDED
+    }
+}
+
+/// Min-cost flow by successive shortest paths: every unit of flow takes the
+/// shortest augmenting path, so `k` units always follow `k` paths of minimal
+/// total length. Dijkstra runs on costs reduced by vertex potentials, which
+/// keeps them non negative despite the reverse arcs
+#[derive(Clone)]
+pub(crate) struct MinCostFlow {
+    network: FlowNetwork,
+    potentials: Vec<i64>,
+}
+
+impl MinCostFlow {
+    pub fn new(graph: &Graph) -> Self {
+        Self::from_network(FlowNetwork::new(graph))
+    }
+
+    /// Costs must not be negative
+    pub fn from_network(network: FlowNetwork) -> Self {
+        Self {
+            potentials: vec![0; network.heads.len()],
+            network,
+        }
+    }
+
+    pub fn paths(&self) -> Vec<Path> {
+        self.network.paths()
+    }
+
+    /// Length of the last augmenting path, by how much it raised the total
+    /// length of the paths: potentials are distances from the source
+    pub fn last_path_cost(&self) -> i64 {
+        self.potentials[self.network.sink]
+    }
+
+    /// Remove a room or a tunnel no flow goes through yet, the flow stays
+    /// of min cost as removing arcs keeps the reduced costs non negative
+    pub fn close(&mut self, element: Element) {
+        match element {
+            Element::Room(id) => self.network.close_arcs(in_vertex(id), out_vertex(id)),
+            Element::Link(a, b) => {
+                self.network.close_arcs(out_vertex(a), in_vertex(b));
+                self.network.close_arcs(out_vertex(b), in_vertex(a));
+            }
+        }
+    }
+
+    /// Whether the flow stays of min cost with a new tunnel between `a` &
+    /// `b`, its arcs having non negative reduced costs both ways
+    pub fn admits_link(&self, a: NodeId, b: NodeId, length: usize) -> bool {
+        let reduced = |from, to| length as i64 + self.potentials[out_vertex(from)]
+            - self.potentials[in_vertex(to)];
+        reduced(a, b) >= 0 && reduced(b, a) >= 0
+    }
+
+    /// Add a tunnel leaving `from`, see [`FlowNetwork::add_link`]
+    pub fn add_link(&mut self, graph: &Graph, from: NodeId, link: Link) {
+        self.network.add_link(graph, from, link);
+    }
+
+    /// Push one more unit of flow, `false` once the flow is maximal
+    pub fn augment(&mut self) -> bool {
+        let FlowNetwork { arcs, heads, source, sink } = &self.network;
+        let mut distances = vec![i64::MAX; heads.len()];
+        let mut parent_arc = vec![None; heads.len()];
+        distances[*source] = 0;
+        let mut queue = BinaryHeap::from([Reverse((0, *source))]);
+        while let Some(Reverse((distance, vertex))) = queue.pop() {
+            if distance > distances[vertex] {
+                continue;
+            }
+            for &arc in &heads[vertex] {
+                let Arc { to, residual, cost } = arcs[arc];
+                let reduced = cost + self.potentials[vertex] - self.potentials[to];
+                if residual != 0 && distance + reduced < distances[to] {
+                    distances[to] = distance + reduced;
+                    parent_arc[to] = Some(arc);
+                    queue.push(Reverse((distances[to], to)));
+                }
+            }
+        }
+
+        if distances[*sink] == i64::MAX {
+            return false;
+        }
+        // Unreachable vertices are taken as far as the farthest reachable
+        // one, which keeps the reduced costs of every arc non negative
+        let farthest = distances.iter().filter(|&&distance| distance != i64::MAX).max();
+        let farthest = *farthest.expect("the source is reachable");
+        for (potential, distance) in self.potentials.iter_mut().zip(distances) {
+            *potential += if distance == i64::MAX { farthest } else { distance };
+        }
+        let mut vertex = self.network.sink;
+        while let Some(arc) = parent_arc[vertex] {
+            self.network.arcs[arc].residual -= 1;
+            self.network.arcs[arc ^ 1].residual += 1;
+            vertex = self.network.arcs[arc ^ 1].to;
+        }
+        true
+    }
+}
+
+fn in_vertex(id: NodeId) -> usize {
+    2 * usize::from(id)
+}
+
+fn out_vertex(id: NodeId) -> usize {
+    2 * usize::from(id) + 1
+}
+
+impl Graph {
+    /// Maximum number of vertex-disjoint paths from start to end
+    /// A room with a capacity greater than one may be shared by that many paths,
+    /// paths are only edge-disjoint with [`Routing::EdgeDisjoint`](super::Routing)
+    pub fn max_disjoint_paths(&self) -> usize {
+        if self.start == self.end {
+            return 0;
+        }
+        FlowNetwork::new(self).max_flow(usize::MAX)
+    }
+
+    /// Smallest set of rooms whose removal separates start from end
+    /// A direct start-end link can not be cut, so the cut may be smaller
+    /// than [`Graph::max_disjoint_paths`] by the number of such links
+    pub fn min_vertex_cut(&self) -> Vec<NodeId> {
+        if self.start == self.end {
+            return Vec::new();
+        }
+        let mut network = FlowNetwork::new(self);
+        network.max_flow(usize::MAX);
+        let reachable = network.residual_reachable();
+        (0..self.nodes.len())
+            .map(NodeId::from)
+            .filter(|&id| reachable[in_vertex(id)] && !reachable[out_vertex(id)])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinCostFlow;
+    use crate::Graph;
+
+    #[test]
+    fn disjoint_paths_of_handmade_maps() {
+        let maps = [
+            (include_str!("../../maps/handmade/subject_map"), 2),
+            (include_str!("../../maps/handmade/bottleneck"), 1),
+            (include_str!("../../maps/handmade/three_route"), 3),
+            (include_str!("../../maps/handmade/duration"), 4),
+        ];
+        for (map, expected) in maps {
+            let graph: Graph = map.parse().unwrap();
+            assert_eq!(graph.max_disjoint_paths(), expected);
+            assert_eq!(graph.min_vertex_cut().len(), expected);
+        }
+    }
+
+    #[test]
+    fn bottleneck_room_is_the_cut() {
+        let graph: Graph = include_str!("../../maps/handmade/bottleneck").parse().unwrap();
+        let cut = graph.min_vertex_cut();
+
+        assert_eq!(cut.len(), 1);
+        assert_eq!(graph[cut[0]].name.as_ref(), "bottleneck");
+    }
+
+    #[test]
+    fn min_cost_flow_reroutes_the_shortest_path() {
+        let graph: Graph = "2\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\nd 0 0\nf 0 0\n##end\ne 0 0\n\
+            s-a\na-b\nb-e\ns-c\nc-b\na-d\nd-f\nf-e"
+            .parse()
+            .unwrap();
+        let mut flow = MinCostFlow::new(&graph);
+        let durations = |flow: &MinCostFlow| {
+            let mut durations: Vec<_> = flow.paths().iter().map(|path| path.duration(&graph)).collect();
+            durations.sort_unstable();
+            durations
+        };
+
+        assert!(flow.augment());
+        assert_eq!(durations(&flow), [3]);
+        assert!(flow.augment());
+        assert_eq!(durations(&flow), [3, 4]);
+        assert!(!flow.augment());
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+    use super::MinCostFlow;
+    use crate::Graph;
+    use test::bench::Bencher;
+
+    #[bench]
+    fn min_cost_flow_thousand(b: &mut Bencher) {
+        let graph: Graph = include_str!("../../maps/generated/flow_thousand").parse().unwrap();
+        b.iter(|| {
+            let mut flow = MinCostFlow::new(&graph);
+            while flow.augment() {}
+            flow.paths()
+        });
+    }
+}
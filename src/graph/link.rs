@@ -3,16 +3,29 @@ use super::{NodeId, name, Name};
 use core::fmt;
 use std::{str::FromStr, error::Error};
 
+/// A tunnel as seen from the room it leaves
+/// An undirected tunnel is stored once in each of the rooms it joins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Link {
+    pub to: NodeId,
+    /// Number of turns needed to go through
+    pub length: usize,
+}
+
 #[derive(PartialEq, Eq)]
 pub struct LinkByName {
     pub a: Name,
     pub b: Name,
+    pub length: usize,
+    /// Only usable from `a` to `b` (`a>b`)
+    pub directed: bool,
 }
 
 #[derive(Debug)]
 pub enum ParseError {
     MissingField,
-    InvalidName(name::ParseError)
+    InvalidName(name::ParseError),
+    InvalidLength(String),
 }
 
 impl fmt::Display for ParseError {
@@ -20,6 +33,7 @@ impl fmt::Display for ParseError {
         match self {
             ParseError::MissingField => write!(f, "Missing field"),
             ParseError::InvalidName(name_error) => write!(f, "Invalid name: {name_error}"),
+            ParseError::InvalidLength(length) => write!(f, "Invalid length {length}"),
         }
     }
 }
@@ -43,11 +57,30 @@ impl FromStr for LinkByName {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (a, b) = s.split_once('-')
-            .ok_or(ParseError::MissingField)?;
+        let (s, length) = match s.split_once(' ') {
+            Some((s, length)) => {
+                let length = length
+                    .parse()
+                    .ok()
+                    .filter(|&length| length != 0)
+                    .ok_or_else(|| ParseError::InvalidLength(length.to_owned()))?;
+                (s, length)
+            }
+            None => (s, 1),
+        };
+        let (a, b, directed) = match s.split_once('-') {
+            Some((a, b)) => (a, b, false),
+            None => {
+                let (a, b) = s.split_once('>')
+                    .ok_or(ParseError::MissingField)?;
+                (a, b, true)
+            }
+        };
         Ok(Self {
             a: a.parse()?,
             b: b.parse()?,
+            length,
+            directed,
         })
     }
 }
\ No newline at end of file
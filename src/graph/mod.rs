@@ -3,23 +3,88 @@ mod link;
 mod name;
 mod node;
 mod solve;
+mod flow;
+mod stats;
+mod simulate;
+mod schedule;
+mod colony;
+mod release;
+mod closure;
+mod check;
+mod time_expanded;
+mod bound;
+mod progress;
+mod portfolio;
+mod curve;
+mod what_if;
+mod sensitivity;
 
 use core::fmt;
 use std::{ops::Index, str::FromStr, error::Error};
 
 pub use node_id::NodeId;
-pub use link::LinkByName;
+pub use link::{Link, LinkByName};
 pub use name::{is_invalid_name_char, Name};
 pub use node::Node;
+pub use stats::Stats;
+pub use simulate::{Move, Simulation};
+pub use solve::{Solution, SolveOptions};
+pub use schedule::{Arrival, Schedule};
+pub use colony::Colony;
+pub use release::Releases;
+pub use closure::{Closure, Element};
+pub use check::CheckError;
+pub use progress::{CancelToken, Progress, ProgressCallback};
+pub use curve::{Breakpoint, TurnCurve};
+pub use what_if::LinkAddition;
+pub use sensitivity::Criticality;
 
 use ParseError::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Graph {
     nodes: Vec<Node>,
     start: NodeId,
     end: NodeId,
+    /// Start rooms of the map, [`Graph::start`] joins them when there are several
+    starts: Vec<NodeId>,
+    /// End rooms of the map, [`Graph::end`] joins them when there are several
+    ends: Vec<NodeId>,
     ant_count: usize,
+    colonies: Vec<Colony>,
+    closures: Vec<Closure>,
+    routing: Routing,
+}
+
+/// What ants may not share during a turn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Routing {
+    /// The subject's rules: a room holds a single ant, or as many as its
+    /// `##capacity` tag allows
+    #[default]
+    VertexDisjoint,
+    /// Rooms hold any number of ants, but a single ant enters a tunnel
+    /// during a turn, whatever its direction
+    EdgeDisjoint,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Reject maps that are not valid per the subject, such as maps with
+    /// several start or end rooms or several colonies
+    pub strict: bool,
+    /// Rules the map is solved with, `##capacity` tags are ignored when
+    /// only tunnels are exclusive
+    pub routing: Routing,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: true,
+            routing: Routing::default(),
+        }
+    }
 }
 
 impl Graph {
@@ -42,15 +107,65 @@ impl Graph {
             .iter()
             .position(|n| n.name == link.b)
             .ok_or(LinkingError::UnknownName(link.b))?;
-        self.nodes[a].links.push(NodeId::from(b));
-        self.nodes[b].links.push(NodeId::from(a));
+        let length = link.length;
+        self.nodes[a].links.push(Link { to: NodeId::from(b), length });
+        if !link.directed {
+            self.nodes[b].links.push(Link { to: NodeId::from(a), length });
+        }
         Ok(())
     }
 
+    /// Turns needed to go from `from` to `to`, if they are linked
+    pub fn link_length(&self, from: NodeId, to: NodeId) -> Option<usize> {
+        self[from]
+            .links
+            .iter()
+            .filter(|link| link.to == to)
+            .map(|link| link.length)
+            .min()
+    }
+
+    /// Join several start (or end) rooms with a virtual room through
+    /// instant tunnels, so the solver only sees one start (or end)
+    fn join_terminals(&mut self, rooms: &[usize], name: &str, outgoing: bool) -> NodeId {
+        if let [room] = rooms {
+            return NodeId::from(*room);
+        }
+        let id = NodeId::from(self.nodes.len());
+        self.nodes.push(Node {
+            name: Name::reserved(name),
+            pos: node::Position { x: 0, y: 0 },
+            links: Vec::new(),
+            capacity: usize::MAX,
+        });
+        for &room in rooms {
+            self.nodes[room].capacity = usize::MAX;
+            if outgoing {
+                self.nodes[usize::from(id)].links.push(Link { to: NodeId::from(room), length: 0 });
+            } else {
+                self.nodes[room].links.push(Link { to: id, length: 0 });
+            }
+        }
+        id
+    }
+
+    /// Whether `id` is a room of the map rather than the virtual one joining
+    /// several start or end rooms
+    fn is_room(&self, id: NodeId) -> bool {
+        (id != self.start && id != self.end) || self.starts.contains(&id) || self.ends.contains(&id)
+    }
+
+    /// Rooms of the map, then the virtual ones joining several start or end
+    /// rooms for the solvers, see [`Graph::start`]
     pub fn nodes(&self) -> &[Node] {
         &self.nodes
     }
 
+    /// Room called `name`
+    pub(crate) fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|node| node.name.as_ref() == name).map(NodeId::from)
+    }
+
     pub fn start(&self) -> NodeId {
         self.start
     }
@@ -59,23 +174,43 @@ impl Graph {
         self.end
     }
 
+    pub fn starts(&self) -> &[NodeId] {
+        &self.starts
+    }
+
+    pub fn ends(&self) -> &[NodeId] {
+        &self.ends
+    }
+
     pub fn ant_count(&self) -> usize {
         self.ant_count
     }
 
+    pub fn routing(&self) -> Routing {
+        self.routing
+    }
+
     // #[cfg(test)]
     pub fn random(mut rng: impl rand::Rng, node_count: usize, link_density: f32, max_ant_count: usize) -> Self {
+        let start = NodeId::from(rng.gen_range(0..node_count));
+        let end = NodeId::from(rng.gen_range(0..node_count));
         Self {
-            start: NodeId::from(rng.gen_range(0..node_count)),
-            end: NodeId::from(rng.gen_range(0..node_count)),
+            start,
+            end,
+            starts: vec![start],
+            ends: vec![end],
+            colonies: Vec::new(),
+            closures: Vec::new(),
+            routing: Routing::default(),
             nodes: (0..node_count)
                 .map(|id| Node {
                     name: Name::from_str(&id.to_string()).unwrap(),
                     pos: node::Position { x: 0, y: 0 },
                     links: (0..node_count)
                         .filter(|_| rng.gen::<f32>() < link_density)
-                        .map(|id| NodeId::from(id))
+                        .map(|id| Link { to: NodeId::from(id), length: 1 })
                         .collect(),
+                    capacity: 1,
                 })
                 .collect(),
             ant_count: rng.gen_range(0..max_ant_count),
@@ -94,6 +229,12 @@ impl FromStr for Graph {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &ParseOptions::default())
+    }
+}
+
+impl Graph {
+    pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Self, ParseError> {
         let mut lines = s.lines();
         let number_of_ants: usize = lines
             .next()
@@ -108,31 +249,92 @@ impl FromStr for Graph {
             ant_count: number_of_ants,
             start: NodeId::from(0),
             end: NodeId::from(0),
+            starts: vec![],
+            ends: vec![],
             nodes: vec![],
+            colonies: vec![],
+            closures: vec![],
+            routing: options.routing,
         };
-        let mut start = None;
-        let mut end = None;
+        let mut starts = Vec::new();
+        let mut ends = Vec::new();
+        let mut capacity = None;
+        // (name, ant count, start, end)
+        let mut colonies: Vec<(String, usize, Option<usize>, Option<usize>)> = Vec::new();
+        // Resolved once every room & link is known
+        let mut closures = Vec::new();
 
         for line in lines {
             if line.starts_with("##") {
                 let name = &line[2..];
+                if let Some(value) = name.strip_prefix("capacity ") {
+                    let value = value
+                        .parse()
+                        .ok()
+                        .filter(|&value| value != 0)
+                        .ok_or_else(|| InvalidCapacity(value.to_owned()))?;
+                    if capacity.replace(value).is_some() {
+                        return Err(DuplicateTag("capacity".to_owned()));
+                    }
+                    continue;
+                }
+                if let Some(closure) = name.strip_prefix("closed ").filter(|_| !options.strict) {
+                    closures.push(closure);
+                    continue;
+                }
+                if let Some(colony) = name.strip_prefix("colony ").filter(|_| !options.strict) {
+                    let (colony, ant_count) = colony
+                        .split_once(' ')
+                        .and_then(|(colony, ant_count)| Some((colony, ant_count.parse().ok()?)))
+                        .ok_or_else(|| InvalidColony(colony.to_owned()))?;
+                    if colonies.iter().any(|(name, ..)| name == colony) {
+                        return Err(DuplicateTag(format!("colony {colony}")));
+                    }
+                    colonies.push((colony.to_owned(), ant_count, None, None));
+                    continue;
+                }
                 let next_node_id = graph.nodes.len();
-                let previous_value = match name {
-                    "start" => start.replace(next_node_id),
-                    "end" => end.replace(next_node_id),
+                if let Some((tag, colony)) = name.split_once(' ').filter(|_| !options.strict) {
+                    let (_, _, start, end) = colonies
+                        .iter_mut()
+                        .find(|(name, ..)| name == colony)
+                        .ok_or_else(|| InvalidTag(name.to_owned()))?;
+                    let tagged = match tag {
+                        "start" => start,
+                        "end" => end,
+                        _ => return Err(InvalidTag(name.to_owned())),
+                    };
+                    if tagged.replace(next_node_id).is_some() {
+                        return Err(DuplicateTag(name.to_owned()));
+                    }
+                    continue;
+                }
+                let tagged = match name {
+                    "start" => &mut starts,
+                    "end" => &mut ends,
                     _ => return Err(InvalidTag(name.to_owned())),
                 };
-                if previous_value.is_some() {
+                if options.strict && !tagged.is_empty() {
                     return Err(DuplicateTag(name.to_owned()));
                 }
+                tagged.push(next_node_id);
                 continue;
             } else if line.starts_with("#") {
                 continue;
             }
 
             if parsing_nodes {
-                match line.parse() {
-                    Ok(node) => graph.add_node(node)?,
+                match line.parse::<Node>() {
+                    Ok(mut node) => {
+                        node.capacity = match options.routing {
+                            Routing::VertexDisjoint => capacity.take().unwrap_or(1),
+                            Routing::EdgeDisjoint => {
+                                capacity = None;
+                                usize::MAX
+                            }
+                        };
+                        graph.add_node(node)?
+                    },
                     Err(_) => parsing_nodes = false,
                 }
             }
@@ -143,14 +345,37 @@ impl FromStr for Graph {
             }
         }
 
-        let Some(start) = start else {
+        if starts.is_empty() {
             return Err(MissingTag("start".to_owned()));
-        };
-        let Some(end) = end else {
+        }
+        if ends.is_empty() {
             return Err(MissingTag("end".to_owned()));
-        };
-        graph.start = NodeId::from(start);
-        graph.end = NodeId::from(end);
+        }
+        if starts.len() > 1 || ends.len() > 1 {
+            if let Some(&room) = starts.iter().find(|room| ends.contains(room)) {
+                return Err(StartIsEnd(graph.nodes[room].name.clone()));
+            }
+        }
+        for (name, ant_count, start, end) in colonies {
+            let start = start.ok_or_else(|| MissingTag(format!("start {name}")))?;
+            let end = end.ok_or_else(|| MissingTag(format!("end {name}")))?;
+            graph.colonies.push(Colony {
+                name,
+                start: NodeId::from(start),
+                end: NodeId::from(end),
+                ant_count,
+            });
+        }
+        for closure in closures {
+            let closure = graph
+                .parse_closure(closure)
+                .ok_or_else(|| InvalidClosure(closure.to_owned()))?;
+            graph.closures.push(closure);
+        }
+        graph.starts = starts.iter().map(|&id| NodeId::from(id)).collect();
+        graph.ends = ends.iter().map(|&id| NodeId::from(id)).collect();
+        graph.start = graph.join_terminals(&starts, "start", true);
+        graph.end = graph.join_terminals(&ends, "end", false);
 
         Ok(graph)
     }
@@ -163,6 +388,12 @@ pub enum ParseError {
     InvalidTag(String),
     DuplicateTag(String),
     MissingTag(String),
+    /// Room tagged both as a start & an end, while there are several of
+    /// either: no turn would be needed to get through it
+    StartIsEnd(Name),
+    InvalidCapacity(String),
+    InvalidColony(String),
+    InvalidClosure(String),
     LinkParseError(link::ParseError),
     LinkingError(LinkingError),
 }
@@ -175,6 +406,10 @@ impl fmt::Display for ParseError {
             InvalidTag(tag) => write!(f, "Invalid tag {tag}"),
             DuplicateTag(tag)=> write!(f, "Duplicate tag {tag}"),
             MissingTag(tag) => write!(f, "Missing tag {tag}"),
+            StartIsEnd(name) => write!(f, "Room {} is both a start & an end", name.as_ref()),
+            InvalidCapacity(capacity) => write!(f, "Invalid capacity {capacity}"),
+            InvalidColony(colony) => write!(f, "Invalid colony {colony}"),
+            InvalidClosure(closure) => write!(f, "Invalid closure {closure}"),
             LinkParseError(link_error) => write!(f, "Could not parse link: {link_error}"),
             LinkingError(linking_error) => write!(f, "Invalid link: {linking_error}"),
         }
@@ -230,6 +465,72 @@ mod tests {
         assert!(matches!(result, Err(MissingAnts)));
     }
 
+    #[test]
+    fn room_capacity() {
+        let graph: Graph = "3\n##start\ns 0 0\n##capacity 2\na 0 0\nb 0 0\n##end\ne 0 0\ns-a\na-e"
+            .parse()
+            .unwrap();
+
+        assert_eq!(graph.nodes()[1].capacity, 2);
+        assert_eq!(graph.nodes()[2].capacity, 1);
+    }
+
+    #[test]
+    fn link_length() {
+        let graph: Graph = "1\n##start\ns 0 0\n##end\ne 0 0\ns-e 3".parse().unwrap();
+
+        assert_eq!(graph.link_length(graph.start(), graph.end()), Some(3));
+        assert_eq!(graph.link_length(graph.end(), graph.start()), Some(3));
+    }
+
+    #[test]
+    fn directed_link() {
+        let graph: Graph = "1\n##start\ns 0 0\n##end\ne 0 0\ns>e".parse().unwrap();
+
+        assert_eq!(graph.link_length(graph.start(), graph.end()), Some(1));
+        assert_eq!(graph.link_length(graph.end(), graph.start()), None);
+    }
+
+    #[test]
+    fn chained_directed_link() {
+        let map = "1\n##start\ns 0 0\nx 0 0\n##end\ne 0 0\ns>x>e";
+        let result = map.parse::<Graph>();
+
+        assert!(matches!(result, Err(LinkParseError(link::ParseError::InvalidName(_)))));
+        assert!("1\n##start\ns 0 0\nx>y 0 0\n##end\ne 0 0\ns-e".parse::<Graph>().is_err());
+    }
+
+    #[test]
+    fn several_starts() {
+        let map = "1\n##start\na 0 0\n##start\nb 0 0\n##end\ne 0 0\na-e\nb-e";
+
+        assert!(matches!(map.parse::<Graph>(), Err(DuplicateTag(_))));
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let graph = Graph::parse_with(map, &options).unwrap();
+        assert_eq!(graph.starts().len(), 2);
+        assert_eq!(graph.ends(), [graph.end()]);
+        assert_eq!(graph.link_length(graph.start(), graph.starts()[1]), Some(0));
+    }
+
+    #[test]
+    fn start_among_ends() {
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let map = "1\n##start\n##end\na 0 0\n##end\ne 0 0\na-e";
+        let lone = "1\n##start\n##end\na 0 0\nb 0 0\na-b";
+
+        assert!(matches!(Graph::parse_with(map, &options), Err(StartIsEnd(_))));
+        let graph = Graph::parse_with(lone, &options).unwrap();
+        assert_eq!(graph.start(), graph.end());
+        assert_eq!(graph.solve().unwrap().turn_count(), 0);
+    }
+
+    #[test]
+    fn invalid_capacity() {
+        let result = "3\n##capacity 0\na 0 0".parse::<Graph>();
+
+        assert!(matches!(result, Err(InvalidCapacity(_))));
+    }
+
     extern crate test;
     use test::bench::Bencher;
 
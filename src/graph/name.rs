@@ -3,7 +3,7 @@ use std::str::FromStr;
 
 /// A valid node name
 /// See [`is_invalid_name_char`]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Name(String);
 
 #[derive(Debug)]
@@ -27,15 +27,23 @@ impl fmt::Display for ParseError {
 
 impl std::error::Error for ParseError {}
 
-/// Invalid characters are `'-'` `' '` & `'#'`
+/// Invalid characters are `'-'` `'>'` `' '` & `'#'`
 /// ```
 /// # use lem_in::is_invalid_name_char;
 /// assert_eq!(is_invalid_name_char(&'a'), false);
 /// assert_eq!(is_invalid_name_char(&'-'), true);
+/// assert_eq!(is_invalid_name_char(&'>'), true);
 /// assert_eq!(is_invalid_name_char(&'#'), true);
 /// ```
 pub fn is_invalid_name_char(c: &char) -> bool {
-    ['-', ' ', '#'].contains(c)
+    ['-', '>', ' ', '#'].contains(c)
+}
+
+impl Name {
+    /// A name no map can use, for rooms added by the solver
+    pub(crate) fn reserved(name: &str) -> Self {
+        Self(format!("#{name}"))
+    }
 }
 
 impl FromStr for Name {
@@ -1,12 +1,14 @@
 use std::{str::FromStr, num::ParseIntError};
 
-use super::{name, Name, NodeId};
+use super::{name, Link, Name};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Node {
     pub name: Name,
     pub pos: Position,
-    pub links: Vec<NodeId>,
+    pub links: Vec<Link>,
+    /// Number of ants the room can hold at once
+    pub capacity: usize,
 }
 
 #[derive(Debug)]
@@ -39,6 +41,7 @@ impl FromStr for Node {
             name: name.parse()?,
             pos,
             links: Vec::new(),
+            capacity: 1,
         })
     }
 }
@@ -55,7 +58,7 @@ impl From<ParseIntError> for PositionParseError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -0,0 +1,209 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    flow::{FlowNetwork, MinCostFlow},
+    CancelToken, Graph, Routing, Solution, SolveOptions,
+};
+use crate::path::Path;
+
+/// Restarts of the randomized strategy
+const RESTARTS: u64 = 8;
+/// A strategy may give up on solutions taking more turns than the fewest
+/// found so far by any of them, which it shares
+type Strategy = fn(&Graph, &SolveOptions, &AtomicUsize) -> Option<Solution>;
+
+/// Strategies raced by [`Graph::solve_portfolio`], by order of preference
+/// when they tie
+const STRATEGIES: [(&str, Strategy); 4] = [
+    ("min-cost", min_cost),
+    ("heuristic", heuristic),
+    ("max-flow", max_flow),
+    ("randomized", randomized),
+];
+
+fn min_cost(graph: &Graph, options: &SolveOptions, best: &AtomicUsize) -> Option<Solution> {
+    graph.solve_below(options, best)
+}
+
+/// Solution made of `paths`, in no particular order
+fn from_paths(graph: &Graph, mut paths: Vec<Path>, cut_short: bool) -> Solution {
+    paths.sort_by_cached_key(|path| path.duration(graph));
+    let mut solution = graph.distribute(paths);
+    solution.cut_short = cut_short;
+    solution
+}
+
+/// The most paths [`Path::n_shortest`] can find
+/// Left out when tunnels are exclusive, as it only keeps rooms apart
+fn heuristic(graph: &Graph, options: &SolveOptions, _: &AtomicUsize) -> Option<Solution> {
+    if graph.routing() == Routing::EdgeDisjoint {
+        return None;
+    }
+    if graph.start == graph.end {
+        return Some(from_paths(graph, Vec::new(), false));
+    }
+    let mut n = graph.throughput_majorant();
+    while n != 0 {
+        if let Some(paths) = Path::n_shortest_with(graph, n, options) {
+            return Some(from_paths(graph, paths, false));
+        }
+        if options.is_late() || options.is_cancelled() {
+            return None;
+        }
+        n -= 1;
+    }
+    None
+}
+
+/// Whatever paths a plain max flow goes through
+fn max_flow(graph: &Graph, _: &SolveOptions, _: &AtomicUsize) -> Option<Solution> {
+    if graph.start == graph.end {
+        return Some(from_paths(graph, Vec::new(), false));
+    }
+    let mut network = FlowNetwork::new(graph);
+    if network.max_flow(usize::MAX) == 0 {
+        return None;
+    }
+    Some(from_paths(graph, network.paths(), false))
+}
+
+/// Min-cost flows where tunnels get random extra costs, so that paths
+/// slightly longer than the shortest ones get a chance
+/// Stops once no restart is left or a solution, its own or another
+/// strategy's, is as good as it gets
+fn randomized(graph: &Graph, options: &SolveOptions, best: &AtomicUsize) -> Option<Solution> {
+    if graph.start == graph.end {
+        return Some(from_paths(graph, Vec::new(), false));
+    }
+    // `usize::MAX` turns stand for nothing found yet
+    let lower_bound = graph.turn_lower_bound().map_or(0, |bound| bound.min(usize::MAX - 1));
+    let mut found: Option<Solution> = None;
+    for seed in 0..RESTARTS {
+        if options.is_cancelled() || (found.is_some() && options.is_late()) {
+            break;
+        }
+        if best.load(Ordering::Relaxed) <= lower_bound {
+            break;
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let network = FlowNetwork::with_costs(graph, |_, link| {
+            4 * link.length as i64 + rng.gen_range(0..4)
+        });
+        let flow = MinCostFlow::from_network(network);
+        let Some((paths, cut_short)) = graph.best_paths(flow, options, best) else {
+            continue;
+        };
+        let solution = from_paths(graph, paths, cut_short);
+        if found.as_ref().is_none_or(|found| solution.turn_count() < found.turn_count()) {
+            found = Some(solution);
+        }
+    }
+    found
+}
+
+impl Graph {
+    /// Race several strategies on their own thread & return the solution
+    /// taking the fewest turns, with the name of the strategy that found it
+    /// An optimal solution, as told by [`Solution::is_optimal`], stops the
+    /// others, otherwise the slowest one is waited for unless there is a
+    /// deadline
+    pub fn solve_portfolio(&self, options: &SolveOptions) -> Option<(Solution, &'static str)> {
+        // Also cancelled by the caller's token
+        let cancel = options.cancel.as_ref().map_or_else(CancelToken::new, CancelToken::child);
+        let shared = SolveOptions {
+            cancel: Some(cancel.clone()),
+            ..options.clone()
+        };
+        let best = AtomicUsize::new(usize::MAX);
+
+        let results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = STRATEGIES
+                .iter()
+                .map(|&(name, strategy)| {
+                    let (shared, cancel, best) = (&shared, &cancel, &best);
+                    scope.spawn(move || {
+                        let solution = strategy(self, shared, best)?;
+                        best.fetch_min(solution.turn_count(), Ordering::Relaxed);
+                        if solution.is_optimal() {
+                            cancel.cancel();
+                        }
+                        Some((solution, name))
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().expect("strategies do not panic"))
+                .collect()
+        });
+
+        results
+            .into_iter()
+            .min_by_key(|(solution, _)| solution.turn_count())
+            .map(|(mut solution, name)| {
+                // Nothing better was left to find
+                solution.cut_short &= !solution.is_optimal();
+                (solution, name)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::randomized;
+    use crate::{CancelToken, Graph, SolveOptions};
+
+    #[test]
+    fn portfolio_matches_the_best_strategy() {
+        let maps = [
+            include_str!("../../maps/handmade/subject_map"),
+            include_str!("../../maps/handmade/duration"),
+            include_str!("../../maps/generated/big"),
+        ];
+        for map in maps {
+            let graph: Graph = map.parse().unwrap();
+            let (solution, strategy) = graph.solve_portfolio(&SolveOptions::default()).unwrap();
+
+            assert!(solution.turn_count() <= graph.solve().unwrap().turn_count());
+            assert!(!strategy.is_empty());
+        }
+    }
+
+    #[test]
+    fn cancelled_portfolio_stops_the_strategies() {
+        let graph: Graph = include_str!("../../maps/handmade/subject_map").parse().unwrap();
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let options = SolveOptions { cancel: Some(cancel), ..SolveOptions::default() };
+
+        // Only the plain max flow does not stop before its first solution
+        let (_, strategy) = graph.solve_portfolio(&options).unwrap();
+        assert_eq!(strategy, "max-flow");
+    }
+
+    #[test]
+    fn strategies_stop_at_the_bound_reached_by_another() {
+        let graph: Graph = "4\n##start\ns 0 0\na 0 0\nb 0 0\n##end\ne 0 0\ns-a\na-e\ns-b\nb-e"
+            .parse()
+            .unwrap();
+        let bound = graph.turn_lower_bound().unwrap();
+        let options = SolveOptions::default();
+
+        let unbounded = randomized(&graph, &options, &AtomicUsize::new(usize::MAX)).unwrap();
+        assert_eq!(unbounded.turn_count(), bound);
+        // Another strategy already found as good as it gets
+        assert!(randomized(&graph, &options, &AtomicUsize::new(bound)).is_none());
+        assert!(graph.solve_below(&options, &AtomicUsize::new(bound)).is_none());
+        // No set of paths can take fewer turns than a worse one found
+        let worse = AtomicUsize::new(bound + 1);
+        assert_eq!(graph.solve_below(&options, &worse).unwrap().turn_count(), bound);
+        assert_eq!(worse.into_inner(), bound);
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Shared flag asking a running solver to stop as soon as it can
+/// Clones share the same flag, so one can be kept to cancel from another
+/// thread while the solver holds the other
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    flag: Arc<AtomicBool>,
+    /// Token cancelling this one along with itself
+    parent: Option<Box<CancelToken>>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token cancelled along with this one, that can also be cancelled on
+    /// its own
+    pub fn child(&self) -> Self {
+        Self { flag: Arc::default(), parent: Some(Box::new(self.clone())) }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+            || self.parent.as_ref().is_some_and(|parent| parent.is_cancelled())
+    }
+}
+
+/// Work done so far by a solver
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub branches_explored: usize,
+    pub valid_paths: usize,
+    /// Turns taken by the best solution found yet
+    pub best_turns: Option<usize>,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
@@ -0,0 +1,161 @@
+use core::fmt;
+use std::{error::Error, str::FromStr};
+
+use super::{flow::MinCostFlow, schedule::route, Graph, Schedule, SolveOptions};
+
+/// Earliest turn each ant may leave start, ants are interchangeable so only
+/// the set of turns matters. A turn `t` lets the ant move on turn `t + 1`
+#[derive(Debug, Clone, Default)]
+pub struct Releases(Vec<usize>);
+
+#[derive(Debug)]
+pub enum ParseError {
+    InvalidTurn(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidTurn(turn) => write!(f, "Invalid turn {turn}"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// One turn per line, `#` starts a comment
+impl FromStr for Releases {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .map(|turn| turn.parse().map_err(|_| ParseError::InvalidTurn(turn.to_owned())))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl From<Vec<usize>> for Releases {
+    fn from(turns: Vec<usize>) -> Self {
+        Self(turns)
+    }
+}
+
+impl Releases {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Path & departure turn of each ant released at sorted `turns`, sent on
+/// the path of `durations` where it arrives first, with the last arrival
+fn depart(durations: &[usize], turns: &[usize]) -> (usize, Vec<(usize, usize)>) {
+    // First turn each path can take a new ant
+    let mut free = vec![0; durations.len()];
+    let mut last_arrival = 0;
+    let departures = turns
+        .iter()
+        .map(|&release| {
+            let (path, departure) = free
+                .iter()
+                .map(|&free| free.max(release))
+                .enumerate()
+                .min_by_key(|&(path, departure)| (departure + durations[path], path))
+                .expect("at least one path");
+            free[path] = departure + 1;
+            last_arrival = last_arrival.max(departure + durations[path]);
+            (path, departure)
+        })
+        .collect();
+    (last_arrival, departures)
+}
+
+impl Graph {
+    /// Schedule ants that only become available over time, ants without a
+    /// release turn are available from the start
+    /// Ants are taken by release turn & sent on the path where they arrive
+    /// first, among the shortest sets of paths of every size (min-cost
+    /// flow) the one bringing the last ant the earliest is kept. Late ants
+    /// may favor fewer, shorter paths than the ones [`Graph::solve_with`]
+    /// picks. Paths are searched within the limits of `options`
+    pub fn solve_with_releases(
+        &self,
+        releases: &Releases,
+        options: &SolveOptions,
+    ) -> Option<Schedule> {
+        if self.start == self.end {
+            return (self.ant_count == 0).then(Schedule::default);
+        }
+        let mut turns: Vec<_> = (0..self.ant_count)
+            .map(|ant| releases.0.get(ant).copied().unwrap_or(0))
+            .collect();
+        turns.sort_unstable();
+
+        let mut flow = MinCostFlow::new(self);
+        let mut best = None;
+        // More paths than ants can not help
+        for _ in 0..self.ant_count.max(1) {
+            if (best.is_some() && options.is_late()) || options.is_cancelled() {
+                break;
+            }
+            if !flow.augment() {
+                break;
+            }
+            let mut paths = flow.paths();
+            paths.sort_by_cached_key(|path| path.duration(self));
+            let durations: Vec<_> = paths.iter().map(|path| path.duration(self)).collect();
+            let (last_arrival, departures) = depart(&durations, &turns);
+            if best.as_ref().is_none_or(|&(best, _, _)| last_arrival < best) {
+                best = Some((last_arrival, paths, departures));
+            }
+        }
+
+        let (_, paths, departures) = best?;
+        let mut schedule = Schedule::default();
+        for (path, departure) in departures {
+            schedule.push(route(self, &paths[path], departure));
+        }
+        Some(schedule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Releases;
+    use crate::{Graph, SolveOptions};
+
+    #[test]
+    fn late_ants_leave_once_released() {
+        let graph: Graph = "3\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a\na-e\ns-b\nb-c\nc-e"
+            .parse()
+            .unwrap();
+        let releases: Releases = "0\n0\n5 # late".parse().unwrap();
+        let schedule = graph.solve_with_releases(&releases, &SolveOptions::default()).unwrap();
+
+        let first_moves: Vec<_> = schedule.routes().iter().map(|route| route[0].0).collect();
+        assert_eq!(first_moves, [1, 2, 6]);
+        assert_eq!(schedule.turn_count(), 7);
+    }
+
+    #[test]
+    fn late_ants_may_use_fewer_paths() {
+        // Two paths of 4 turns suit ants all released at once, the late one
+        // arrives earlier on the path of 3 turns they share a room with
+        let graph: Graph = "4\n##start\ns 0 0\na 0 0\nb 0 0\nx 0 0\ny 0 0\nc 0 0\nd 0 0\n\
+            ##end\ne 0 0\ns-a\na-b\nb-e\na-x\nx-y\ny-e\ns-c\nc-d\nd-b"
+            .parse()
+            .unwrap();
+        let releases: Releases = "0\n0\n0\n100".parse().unwrap();
+        let schedule = graph.solve_with_releases(&releases, &SolveOptions::default()).unwrap();
+
+        assert_eq!(schedule.turn_count(), 103);
+        assert_eq!(schedule.routes().len(), 4);
+    }
+}
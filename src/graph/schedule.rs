@@ -0,0 +1,229 @@
+use std::{collections::{HashMap, HashSet}, io};
+
+use super::{Graph, Move, NodeId, Routing, Solution};
+use crate::path::Path;
+
+/// Turn (starting at 1) at which an ant enters a room
+pub type Arrival = (usize, NodeId);
+
+/// Explicit moves of every ant, for solutions that are not a plain set of
+/// paths fed one ant per turn
+#[derive(Debug, Default, Clone)]
+pub struct Schedule {
+    routes: Vec<Vec<Arrival>>,
+}
+
+impl Schedule {
+    /// Arrivals of each ant, ant `i` is printed as `L{i + 1}`
+    pub fn routes(&self) -> &[Vec<Arrival>] {
+        &self.routes
+    }
+
+    pub fn push(&mut self, route: Vec<Arrival>) {
+        self.routes.push(route);
+    }
+
+    pub fn turn_count(&self) -> usize {
+        self.routes
+            .iter()
+            .filter_map(|route| route.last())
+            .map(|&(turn, _)| turn)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn turns(&self) -> Vec<Vec<Move>> {
+        let mut turns = vec![Vec::new(); self.turn_count()];
+        for (ant, route) in self.routes.iter().enumerate() {
+            for &(turn, node) in route {
+                turns[turn - 1].push(Move { ant: ant + 1, node });
+            }
+        }
+        turns
+    }
+
+    // Print to the correct format
+    pub fn write_to(&self, graph: &Graph, mut output: impl io::Write) -> io::Result<()> {
+        for moves in self.turns() {
+            let moves: Vec<_> = moves
+                .iter()
+                .map(|Move { ant, node }| format!("L{ant}-{}", graph[*node].name.as_ref()))
+                .collect();
+            writeln!(output, "{}", moves.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Solution {
+    pub fn to_schedule(&self, graph: &Graph) -> Schedule {
+        let mut schedule = Schedule::default();
+        for (turn, moves) in self.simulate(graph).enumerate() {
+            for Move { ant, node } in moves {
+                if schedule.routes.len() < ant {
+                    schedule.routes.resize(ant, Vec::new());
+                }
+                schedule.routes[ant - 1].push((turn + 1, node));
+            }
+        }
+        schedule
+    }
+}
+
+/// Arrivals of an ant leaving start on turn `departure + 1` along `path`
+/// Instant tunnels are not moves so they are left out
+pub(crate) fn route(graph: &Graph, path: &Path, departure: usize) -> Vec<Arrival> {
+    let mut turn = departure;
+    let mut from = graph.start();
+    let mut route = Vec::new();
+    for &to in path.as_ref() {
+        let length = graph.link_length(from, to).expect("path follows links");
+        turn += length;
+        if length != 0 {
+            route.push((turn, to));
+        }
+        from = to;
+    }
+    route
+}
+
+/// Where an ant is during a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stay {
+    /// In a room at the end of the turn
+    Room(NodeId, usize),
+    /// Going through the tunnel from the first room to the second one
+    Tunnel(NodeId, NodeId, usize),
+    /// Entering the tunnel from the first room to the second one
+    Entry(NodeId, NodeId, usize),
+}
+
+impl Stay {
+    pub fn is_open(self, graph: &Graph) -> bool {
+        match self {
+            Stay::Room(room, turn) => !graph.is_room_closed(room, turn),
+            Stay::Tunnel(from, to, turn) | Stay::Entry(from, to, turn) => {
+                !graph.is_link_closed(from, to, turn)
+            }
+        }
+    }
+
+    pub fn turn(self) -> usize {
+        match self {
+            Stay::Room(_, turn) | Stay::Tunnel(_, _, turn) | Stay::Entry(_, _, turn) => turn,
+        }
+    }
+}
+
+impl Graph {
+    /// Turns taken by a printed move from `from` to `to`, the move may go
+    /// through an instant tunnel first when `from` joins several start rooms
+    pub(crate) fn hop_length(&self, from: NodeId, to: NodeId) -> Option<usize> {
+        let direct = self.link_length(from, to).filter(|&length| length != 0);
+        direct.or_else(|| {
+            self[from]
+                .links
+                .iter()
+                .filter(|link| link.length == 0)
+                .find_map(|link| self.link_length(link.to, to))
+                .filter(|&length| length != 0)
+        })
+    }
+}
+
+/// Every turn an ant leaving `from` spends in a room or a tunnel along
+/// `route`, the ant waits in a room until the last turn it can leave it
+pub(crate) fn stays(graph: &Graph, from: NodeId, route: &[Arrival]) -> Vec<Stay> {
+    let mut stays = Vec::new();
+    let mut previous: Option<Arrival> = None;
+    let mut from = from;
+    for &(turn, node) in route {
+        let length = graph.hop_length(from, node).unwrap_or(1);
+        let departure = (turn + 1).saturating_sub(length);
+        if let Some((arrival, room)) = previous {
+            stays.extend((arrival..departure).map(|turn| Stay::Room(room, turn)));
+        }
+        stays.push(Stay::Entry(from, node, departure));
+        stays.extend((departure..=turn).map(|turn| Stay::Tunnel(from, node, turn)));
+        previous = Some((turn, node));
+        from = node;
+    }
+    if let Some((turn, room)) = previous {
+        stays.push(Stay::Room(room, turn));
+    }
+    stays
+}
+
+/// Rooms & tunnels already used by scheduled ants, turn by turn
+#[derive(Debug, Default)]
+pub(crate) struct Reservations {
+    rooms: HashMap<(NodeId, usize), usize>,
+    /// (from, to, turn) of every move through a single turn tunnel
+    tunnels: HashSet<(NodeId, NodeId, usize)>,
+    /// (room, room, turn) of every ant entering a tunnel, smaller room first
+    entries: HashSet<(NodeId, NodeId, usize)>,
+    /// Start & end rooms of the ants being placed, they can be there in any
+    /// number & leave the room to the ants of other colonies
+    terminals: HashSet<NodeId>,
+    last_turn: usize,
+}
+
+impl Reservations {
+    /// Place the ants of another colony from now on
+    pub fn set_terminals(&mut self, terminals: impl IntoIterator<Item = NodeId>) {
+        self.terminals = terminals.into_iter().collect();
+    }
+
+    /// Last turn any reserved ant is still on its way
+    pub fn last_turn(&self) -> usize {
+        self.last_turn
+    }
+
+    /// Whether an ant can be there without overfilling a room, crossing
+    /// another ant in a tunnel or entering a tunnel with another ant when
+    /// tunnels are exclusive, closures aside
+    pub fn allows(&self, graph: &Graph, stay: Stay) -> bool {
+        match stay {
+            Stay::Room(room, turn) => {
+                let load = self.rooms.get(&(room, turn)).copied().unwrap_or(0);
+                self.terminals.contains(&room) || load < graph[room].capacity
+            }
+            Stay::Tunnel(from, to, turn) => {
+                graph.hop_length(from, to) != Some(1) || !self.tunnels.contains(&(to, from, turn))
+            }
+            Stay::Entry(from, to, turn) => {
+                graph.routing() != Routing::EdgeDisjoint
+                    || !self.entries.contains(&(from.min(to), from.max(to), turn))
+            }
+        }
+    }
+
+    /// Whether an ant leaving `from` can follow `route` without using a
+    /// closed room or tunnel nor getting in the way of reserved ants
+    pub fn fits(&self, graph: &Graph, from: NodeId, route: &[Arrival]) -> bool {
+        stays(graph, from, route)
+            .into_iter()
+            .all(|stay| stay.is_open(graph) && self.allows(graph, stay))
+    }
+
+    pub fn reserve(&mut self, graph: &Graph, from: NodeId, route: &[Arrival]) {
+        for stay in stays(graph, from, route) {
+            match stay {
+                Stay::Room(room, turn) => {
+                    if !self.terminals.contains(&room) {
+                        *self.rooms.entry((room, turn)).or_default() += 1;
+                    }
+                    self.last_turn = self.last_turn.max(turn);
+                }
+                Stay::Tunnel(from, to, turn) => {
+                    if graph.hop_length(from, to) == Some(1) {
+                        self.tunnels.insert((from, to, turn));
+                    }
+                }
+                Stay::Entry(from, to, turn) => {
+                    self.entries.insert((from.min(to), from.max(to), turn));
+                }
+            }
+        }
+    }
+}
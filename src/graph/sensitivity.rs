@@ -0,0 +1,160 @@
+use std::{cmp::Reverse, collections::HashSet, io};
+
+use super::{curve::TurnCurve, flow::MinCostFlow, Element, Graph};
+use crate::path::Path;
+
+/// Turns the ants of the map would lose without an element of the paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Criticality {
+    pub element: Element,
+    /// `None` when the end could no longer be reached
+    pub added_turns: Option<usize>,
+}
+
+impl Graph {
+    /// Copy of the map without `element`, rooms keep their ids & only lose
+    /// their tunnels
+    pub fn without(&self, element: Element) -> Graph {
+        let mut graph = self.clone();
+        for (id, node) in graph.nodes.iter_mut().enumerate() {
+            node.links.retain(|link| match element {
+                Element::Room(room) => usize::from(room) != id && link.to != room,
+                Element::Link(a, b) => {
+                    let from = usize::from(a) == id || usize::from(b) == id;
+                    !(from && (link.to == a || link.to == b))
+                }
+            });
+        }
+        graph
+    }
+
+    /// Rooms & tunnels of `path`, start & end aside
+    fn elements(&self, path: &Path) -> Vec<Element> {
+        let mut elements = Vec::new();
+        let mut from = self.start;
+        for &to in path.as_ref() {
+            if self.is_room(from) && self.is_room(to) {
+                elements.push(Element::Link(from.min(to), from.max(to)));
+            }
+            if to != self.end && self.is_room(to) {
+                elements.push(Element::Room(to));
+            }
+            from = to;
+        }
+        elements
+    }
+
+    /// Rooms & tunnels of the paths of [`Graph::solve`], start & end aside,
+    /// with the turns added without each of them, most critical first
+    /// Min-cost flows that do not go through an element are still of min
+    /// cost without it, so the search without it resumes from the last of
+    /// them rather than from scratch
+    pub fn sensitivity(&self) -> Vec<Criticality> {
+        if self.start == self.end || self.ant_count == 0 {
+            return Vec::new();
+        }
+        let mut snapshots = Vec::new();
+        let flow = MinCostFlow::new(self);
+        let Some(base) = TurnCurve::min_turns_from(&[], flow, self.ant_count, Some(&mut snapshots))
+        else {
+            return Vec::new();
+        };
+        let curve = TurnCurve::from_snapshots(&snapshots);
+        // Elements each flow goes through, the first one without any
+        let used: Vec<HashSet<_>> = snapshots
+            .iter()
+            .map(|flow| flow.paths().iter().flat_map(|path| self.elements(path)).collect())
+            .collect();
+
+        let mut elements = Vec::new();
+        for path in self.solve().map(|solution| solution.paths().to_vec()).unwrap_or_default() {
+            for element in self.elements(&path) {
+                if !elements.contains(&element) {
+                    elements.push(element);
+                }
+            }
+        }
+        let mut report: Vec<_> = elements
+            .into_iter()
+            .map(|element| {
+                let first = used.iter().position(|used| used.contains(&element));
+                let added_turns = match first {
+                    Some(first) => {
+                        let mut flow = snapshots[first - 1].clone();
+                        flow.close(element);
+                        let costs = &curve.costs()[..first - 1];
+                        TurnCurve::min_turns_from(costs, flow, self.ant_count, None)
+                            .map(|turns| turns - base)
+                    }
+                    None => Some(0),
+                };
+                Criticality { element, added_turns }
+            })
+            .collect();
+        report.sort_by_key(|criticality| Reverse(criticality.added_turns.unwrap_or(usize::MAX)));
+        report
+    }
+
+    /// Print [`Graph::sensitivity`], one element per line
+    pub fn write_sensitivity(&self, mut output: impl io::Write) -> io::Result<()> {
+        for Criticality { element, added_turns } in self.sensitivity() {
+            let element = match element {
+                Element::Room(id) => format!("Room {}", self[id].name.as_ref()),
+                Element::Link(a, b) => {
+                    format!("Tunnel {}-{}", self[a].name.as_ref(), self[b].name.as_ref())
+                }
+            };
+            match added_turns {
+                Some(turns) => writeln!(output, "{element}: +{turns} turns")?,
+                None => writeln!(output, "{element}: the end can no longer be reached")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Criticality;
+    use crate::{Element, Graph};
+
+    #[test]
+    fn bridges_are_the_most_critical() {
+        let graph: Graph = "4\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\nd 0 0\n##end\ne 0 0\n\
+            s-a\na-b\nb-e\na-c\nc-d\nd-e"
+            .parse()
+            .unwrap();
+        let id = |name| graph.find(name).unwrap();
+        let report = graph.sensitivity();
+        let added = |element| {
+            report.iter().find(|criticality| criticality.element == element).unwrap().added_turns
+        };
+
+        let s_a = Element::Link(id("s"), id("a"));
+        assert_eq!(report[0], Criticality { element: s_a, added_turns: None });
+        assert_eq!(added(Element::Room(id("a"))), None);
+        assert_eq!(added(Element::Room(id("b"))), Some(1));
+        assert_eq!(added(Element::Link(id("b"), id("e"))), Some(1));
+        assert!(report.iter().all(|criticality| criticality.element != Element::Room(id("c"))));
+        assert_eq!(graph.without(Element::Room(id("b"))).solve().unwrap().turn_count(), 7);
+    }
+
+    #[test]
+    fn resumed_searches_match_searches_from_scratch() {
+        let maps = [
+            include_str!("../../maps/handmade/subject_map"),
+            include_str!("../../maps/handmade/three_route"),
+            include_str!("../../maps/handmade/duration"),
+            include_str!("../../maps/handmade/multiple_stage_transfer"),
+        ];
+        for map in maps {
+            let graph: Graph = map.parse().unwrap();
+            let turns = |graph: &Graph| graph.turn_curve().min_turns(graph.ant_count());
+            let base = turns(&graph).unwrap();
+            for Criticality { element, added_turns } in graph.sensitivity() {
+                let expected = turns(&graph.without(element)).map(|turns| turns - base);
+                assert_eq!(added_turns, expected);
+            }
+        }
+    }
+}
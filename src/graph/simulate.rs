@@ -0,0 +1,150 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{Graph, NodeId, Routing};
+use crate::path::Path;
+
+/// An ant entering a room during a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub ant: usize,
+    pub node: NodeId,
+}
+
+struct Ant {
+    id: usize,
+    path: usize,
+    /// Index in the path of the last room reached, `None` while still in start
+    position: Option<usize>,
+    /// Whether the ant left its room & is going through a tunnel
+    in_tunnel: bool,
+    /// Length of the tunnel the ant is in or came from
+    hop_length: usize,
+    /// Turns left before reaching the end of the tunnel
+    remaining: usize,
+}
+
+/// Turn by turn replay of ants departing along paths
+/// An ant only enters a room that has space left, so no room ever holds
+/// more ants than its capacity. Ants inside a tunnel occupy no room, instant
+/// tunnels are not moves (they join several start or end rooms)
+/// With [`Routing::EdgeDisjoint`], an ant only enters a tunnel no other ant
+/// entered during the turn
+pub struct Simulation<'a> {
+    graph: &'a Graph,
+    paths: &'a [Path],
+    /// (turn, path index) sorted by turn
+    departures: VecDeque<(usize, usize)>,
+    ants: Vec<Ant>,
+    occupancy: Vec<usize>,
+    /// Tunnels entered during the turn, smaller room first
+    entered: HashSet<(NodeId, NodeId)>,
+    turn: usize,
+    next_ant_id: usize,
+}
+
+impl<'a> Simulation<'a> {
+    pub(crate) fn new(
+        graph: &'a Graph,
+        paths: &'a [Path],
+        departures: VecDeque<(usize, usize)>,
+    ) -> Self {
+        Self {
+            graph,
+            paths,
+            departures,
+            ants: Vec::new(),
+            occupancy: vec![0; graph.nodes().len()],
+            entered: HashSet::new(),
+            turn: 0,
+            next_ant_id: 1,
+        }
+    }
+}
+
+impl<'a> Iterator for Simulation<'a> {
+    type Item = Vec<Move>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ants.is_empty() && self.departures.is_empty() {
+            return None;
+        }
+        while let Some(&(turn, path)) = self.departures.front() {
+            if turn > self.turn {
+                break;
+            }
+            self.departures.pop_front();
+            self.ants.push(Ant {
+                id: self.next_ant_id,
+                path,
+                position: None,
+                in_tunnel: false,
+                hop_length: 0,
+                remaining: 0,
+            });
+            self.next_ant_id += 1;
+        }
+        self.turn += 1;
+
+        // Oldest ants go first so they free the rooms their followers enter
+        let Self { graph, paths, occupancy, entered, .. } = self;
+        entered.clear();
+        let mut moves = Vec::new();
+        self.ants.retain_mut(|ant| {
+            let path = paths[ant.path].as_ref();
+            // Whether the ant already spent this turn
+            let mut advanced = false;
+            loop {
+                let next = ant.position.map_or(0, |position| position + 1);
+                let node = path[next];
+                if !ant.in_tunnel {
+                    let from = ant.position.map_or(graph.start(), |position| path[position]);
+                    let length = graph.link_length(from, node).expect("path follows links");
+                    if advanced && length != 0 {
+                        return true;
+                    }
+                    let tunnel = (from.min(node), from.max(node));
+                    let exclusive = graph.routing() == Routing::EdgeDisjoint && length != 0;
+                    if exclusive && !entered.insert(tunnel) {
+                        return true;
+                    }
+                    if ant.position.is_some() {
+                        occupancy[usize::from(from)] -= 1;
+                    }
+                    ant.in_tunnel = true;
+                    ant.hop_length = length;
+                    ant.remaining = length;
+                }
+                if ant.remaining != 0 {
+                    ant.remaining -= 1;
+                    advanced = true;
+                    if ant.remaining != 0 {
+                        return true;
+                    }
+                }
+                if node != graph.end() {
+                    let occupancy = &mut occupancy[usize::from(node)];
+                    if *occupancy >= graph[node].capacity {
+                        return true;
+                    }
+                    *occupancy += 1;
+                }
+                ant.position = Some(next);
+                ant.in_tunnel = false;
+                if ant.hop_length != 0 {
+                    advanced = true;
+                    moves.push(Move { ant: ant.id, node });
+                }
+                if node == graph.end() {
+                    return false;
+                }
+            }
+        });
+
+        // Nothing can move anymore, stop rather than spin forever
+        let travelling = self.ants.iter().any(|ant| ant.in_tunnel && ant.remaining != 0);
+        if moves.is_empty() && !travelling && self.departures.is_empty() {
+            return None;
+        }
+        Some(moves)
+    }
+}
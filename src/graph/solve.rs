@@ -1,7 +1,15 @@
 use core::fmt;
-use std::{collections::VecDeque, io};
+use std::{
+    collections::{HashSet, VecDeque},
+    io,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
 
-use super::Graph;
+use super::{
+    curve::TurnCurve, flow::MinCostFlow, CancelToken, Graph, Move, Progress, ProgressCallback,
+    Routing, Simulation,
+};
 use crate::path::Path;
 
 #[derive(Debug)]
@@ -11,72 +19,145 @@ struct Step {
 }
 
 #[derive(Debug)]
-pub struct Solution(Vec<Step>);
+pub struct Solution {
+    steps: Vec<Step>,
+    turn_count: usize,
+    /// See [`Graph::turn_lower_bound`], or the fewest turns of the turn
+    /// curve when the solution takes more than it, which no schedule beats
+    lower_bound: Option<usize>,
+    /// Whether the search stopped before trying everything
+    pub(crate) cut_short: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct SolveOptions {
+    /// Return the best solution found so far once this instant is passed
+    pub deadline: Option<Instant>,
+    /// Return the best solution found so far, if any, once cancelled
+    pub cancel: Option<CancelToken>,
+    /// Called every now & then with the work done so far
+    pub progress: Option<ProgressCallback>,
+}
 
-fn cycle(vec: &mut VecDeque<Option<usize>>, x: Option<usize>) {
-    vec.pop_back();
-    vec.push_front(x);
+impl fmt::Debug for SolveOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolveOptions")
+            .field("deadline", &self.deadline)
+            .field("cancel", &self.cancel)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
 }
 
-// Print to the correct format
-fn print(
-    output: &mut impl io::Write,
-    path: &Path,
-    ant_vec: &VecDeque<Option<usize>>,
-) -> io::Result<()> {
-    for (node, ant) in path.as_ref().iter().zip(ant_vec) {
-        if let Some(ant) = ant {
-            write!(output, "L{}-{} ", ant, usize::from(*node))?;
+impl SolveOptions {
+    pub(crate) fn is_late(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    pub(crate) fn report(&self, progress: &Progress) {
+        if let Some(callback) = &self.progress {
+            callback(progress);
         }
     }
-    Ok(())
 }
 
 impl Solution {
-    pub fn write_to(&self, mut output: impl io::Write) -> io::Result<()> {
-        // Skip ahead & prevent panic
-        let Some(all_path_step) = self.0.get(0) else {
-            return Ok(());
-        };
-
-        // Prefill with None
-        let mut ant_vecs: Vec<VecDeque<Option<usize>>> = all_path_step
-            .paths
-            .iter()
-            .map(|_| (0..all_path_step.paths.len()).map(|_| None).collect())
-            .collect();
+    /// Paths used by the solution, every step uses a prefix of them
+    pub fn paths(&self) -> &[Path] {
+        self.steps.first().map_or(&[], |step| &step.paths)
+    }
 
-        // Id generator
-        let mut next_ant_id = {
-            let mut id = 0;
-            move || {
-                let result = id;
-                id += 1;
-                result
+    /// (turn, path index) at which each ant leaves start, turns start at 0
+    pub(crate) fn departures(&self) -> VecDeque<(usize, usize)> {
+        let mut departures = VecDeque::new();
+        let mut turn = 0;
+        for step in &self.steps {
+            for _ in 0..step.duration {
+                departures.extend((0..step.paths.len()).map(|path| (turn, path)));
+                turn += 1;
             }
-        };
+        }
+        departures
+    }
 
-        // Push In and Forward ant in Paths + Print
-        for current_step in &self.0 {
-            for _ in 0..current_step.duration {
-                for (i, (path, ants)) in all_path_step.paths.iter().zip(&mut ant_vecs).enumerate() {
-                    cycle(ants, current_step.paths.get(i).map(|_| next_ant_id()));
-                    print(&mut output, path, ants)?;
-                }
-                write!(output, "\n")?;
+    /// Ants sent along each of [`Solution::paths`], one per turn from the
+    /// first turn on
+    pub fn ants_per_path(&self) -> Vec<usize> {
+        let mut ants = vec![0; self.paths().len()];
+        for step in &self.steps {
+            for count in &mut ants[..step.paths.len()] {
+                *count += step.duration;
             }
         }
+        ants
+    }
 
-        // Push forward remaining ant in Paths + Print
-        let latency = all_path_step.paths
-            .iter().map(Path::len)
-            .max().unwrap_or(0);
-        for _ in 0..latency {
-            for (path, ants) in all_path_step.paths.iter().zip(&mut ant_vecs) {
-                cycle(ants, None);
-                print(&mut output, path, ants)?;
-            }
-            write!(output, "\n")?;
+    pub fn turn_count(&self) -> usize {
+        self.turn_count
+    }
+
+    /// Ants the solution brings to the end
+    pub fn ant_count(&self) -> usize {
+        self.ants_per_path().iter().sum()
+    }
+
+    pub fn lower_bound(&self) -> Option<usize> {
+        self.lower_bound
+    }
+
+    /// Whether the solver ran out of time, so a better solution may exist
+    pub fn is_cut_short(&self) -> bool {
+        self.cut_short
+    }
+
+    /// Whether no solution can take fewer turns
+    pub fn is_optimal(&self) -> bool {
+        self.lower_bound == Some(self.turn_count)
+    }
+
+    pub fn simulate<'a>(&'a self, graph: &'a Graph) -> Simulation<'a> {
+        Simulation::new(graph, self.paths(), self.departures())
+    }
+
+    // Print to the correct format
+    pub fn write_to(&self, graph: &Graph, mut output: impl io::Write) -> io::Result<()> {
+        for moves in self.simulate(graph) {
+            let moves: Vec<_> = moves
+                .iter()
+                .map(|Move { ant, node }| format!("L{ant}-{}", graph[*node].name.as_ref()))
+                .collect();
+            writeln!(output, "{}", moves.join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Print the turn count, the paths & when ants leave along them rather
+    /// than every move, for ant counts too large to print
+    pub fn write_summary(&self, graph: &Graph, mut output: impl io::Write) -> io::Result<()> {
+        let ants = self.ants_per_path();
+        writeln!(output, "{} ants in {} turns", self.ant_count(), self.turn_count)?;
+        for (index, (path, ants)) in self.paths().iter().zip(&ants).enumerate() {
+            let rooms: Vec<_> = path.as_ref().iter().map(|&id| graph[id].name.as_ref()).collect();
+            let duration = path.duration(graph);
+            let rooms = rooms.join(" ");
+            writeln!(output, "Path {}: {ants} ants, {duration} turns, {rooms}", index + 1)?;
+        }
+        let (mut turn, mut ant) = (1, 1);
+        for Step { duration, paths } in &self.steps {
+            let last_ant = ant + duration * paths.len() - 1;
+            let last_turn = turn + duration - 1;
+            let paths = match paths.len() {
+                1 => "path 1".to_owned(),
+                count => format!("paths 1-{count}"),
+            };
+            let ants = format!("L{ant} to L{last_ant}");
+            writeln!(output, "Turns {turn}-{last_turn}: {ants} leave along {paths}")?;
+            turn += duration;
+            ant = last_ant + 1;
         }
         Ok(())
     }
@@ -84,7 +165,7 @@ impl Solution {
 
 impl fmt::Display for Solution {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for Step { duration, paths } in &self.0 {
+        for Step { duration, paths } in &self.steps {
             writeln!(f, "{duration} times:")?;
             for path in paths {
                 writeln!(f, "    - {path:?}")?;
@@ -94,35 +175,165 @@ impl fmt::Display for Solution {
     }
 }
 
+/// Turns needed by `ant_count` ants over paths of sorted `durations`, when
+/// the longest paths are left out if they do not help
+fn turn_count(durations: &[usize], ant_count: usize) -> usize {
+    if ant_count == 0 {
+        return 0;
+    }
+    // Wide enough for any ant count plus waits
+    let mut waits = 0u128;
+    (1..=durations.len())
+        .map(|count| {
+            let longest = durations[count - 1];
+            waits += longest as u128 - 1;
+            let turns = (ant_count as u128 + waits).div_ceil(count as u128);
+            usize::try_from(turns).map_or(usize::MAX, |turns| longest.max(turns))
+        })
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
 impl Graph {
     pub fn simple_throughput_majorant(&self) -> usize {
         let start_link_count = self[self.start].links.len();
-        let end_link_count = self[self.start].links.len();
+        let end_link_count = self
+            .nodes
+            .iter()
+            .flat_map(|node| &node.links)
+            .filter(|link| link.to == self.end)
+            .count();
         start_link_count.min(end_link_count)
     }
 
-    pub fn solve(&self) -> Option<Solution> {
-        // TODO: use better one found on the fly
-        let mut n = self.simple_throughput_majorant();
-        let mut paths = loop {
-            if let Some(paths) = Path::n_shortest(self, n) {
-                break paths;
+    /// Tight bound on the number of paths usable at once, see [`Graph::min_vertex_cut`]
+    pub fn throughput_majorant(&self) -> usize {
+        self.max_disjoint_paths()
+    }
+
+    /// Paths taking the fewest turns among the shortest sets of paths of
+    /// every size (min-cost flow), sorted by duration
+    /// Also returns whether the deadline stopped the search early, the
+    /// fewest turns found so far are shared in `best`, see
+    /// [`Graph::best_paths`]
+    pub(crate) fn find_paths(
+        &self,
+        options: &SolveOptions,
+        best: &AtomicUsize,
+    ) -> Option<(Vec<Path>, bool)> {
+        if self.start == self.end {
+            return Some((Vec::new(), false));
+        }
+        self.best_paths(MinCostFlow::new(self), options, best)
+    }
+
+    /// Best set of paths among the successive ones of `flow`, `None` when
+    /// all of them take more turns than `shared`
+    /// `shared` holds the fewest turns found so far, by this search or by
+    /// others: sets of paths that can not match it are not distributed, &
+    /// the search stops once [`Graph::turn_lower_bound`] reaches it
+    pub(crate) fn best_paths(
+        &self,
+        mut flow: MinCostFlow,
+        options: &SolveOptions,
+        shared: &AtomicUsize,
+    ) -> Option<(Vec<Path>, bool)> {
+        // `usize::MAX` turns stand for nothing found yet
+        let lower_bound = self.turn_lower_bound().map_or(0, |bound| bound.min(usize::MAX - 1));
+        let mut best: Option<(usize, Vec<Path>)> = None;
+        let mut progress = Progress::default();
+        // More paths than ants can not help
+        for _ in 0..self.ant_count.max(1) {
+            if (best.is_some() && options.is_late()) || options.is_cancelled() {
+                return best.map(|(_, paths)| (paths, true));
             }
-            n -= 1;
-            if n == 0 {
-                return None;
+            if shared.load(Ordering::Relaxed) <= lower_bound || !flow.augment() {
+                break;
             }
-        };
+            let mut paths = flow.paths();
+            paths.sort_by_cached_key(|path| path.duration(self));
+            let durations: Vec<_> = paths.iter().map(|path| path.duration(self)).collect();
+            // The first ant arrives after the shortest path at best, & every
+            // turn at most one ant per path follows
+            let ants_per_path = self.ant_count.div_ceil(durations.len()).max(1);
+            let quickest = durations[0].saturating_add(ants_per_path - 1);
+            if quickest <= shared.load(Ordering::Relaxed) {
+                let turns = turn_count(&durations, self.ant_count);
+                shared.fetch_min(turns, Ordering::Relaxed);
+                if best.as_ref().is_none_or(|&(best, _)| turns < best) {
+                    best = Some((turns, paths));
+                }
+            }
+            progress.branches_explored += 1;
+            progress.valid_paths = durations.len();
+            progress.best_turns = best.as_ref().map(|&(turns, _)| turns);
+            options.report(&progress);
+        }
+        best.map(|(_, paths)| (paths, false))
+    }
 
-        // TODO: get the paths to be pre sorted
-        paths.sort_by_key(Path::len);
+    pub fn solve(&self) -> Option<Solution> {
+        self.solve_with(&SolveOptions::default())
+    }
+
+    /// Solve within the limits of `options`, the first valid solution is
+    /// always waited for unless the solver is cancelled
+    pub fn solve_with(&self, options: &SolveOptions) -> Option<Solution> {
+        self.solve_below(options, &AtomicUsize::new(usize::MAX))
+    }
+
+    /// Same as [`Graph::solve_with`], `None` when no solution takes as few
+    /// turns as `best`, which is lowered to the turns of the solution found
+    pub(crate) fn solve_below(
+        &self,
+        options: &SolveOptions,
+        best: &AtomicUsize,
+    ) -> Option<Solution> {
+        let (paths, cut_short) = self.find_paths(options, best)?;
+        let mut solution = self.distribute(paths);
+        solution.cut_short = cut_short;
+        Some(solution)
+    }
+
+    /// Most ants reaching the end within `turns` turns, whatever the ant
+    /// count of the map, along the min-cost paths letting the most through
+    /// `None` when no ant can make it in time, or when start is the end as
+    /// any number of them can
+    pub fn solve_within(&self, turns: usize) -> Option<Solution> {
+        if self.start == self.end {
+            return None;
+        }
+        // A path taking more than `turns` turns brings no ant in time
+        let mut paths = Vec::new();
+        let flow = MinCostFlow::new(self);
+        let curve = TurnCurve::from_flow(flow, turns.saturating_add(1), Some(&mut paths));
+        if paths.is_empty() {
+            return None;
+        }
+        paths.sort_by_cached_key(|path| path.duration(self));
+        Some(self.distribute_ants(paths, curve.max_ants(turns)))
+    }
+
+    /// Split the ants between `paths`, sorted by duration, so the last one
+    /// arrives as soon as possible
+    pub(crate) fn distribute(&self, paths: Vec<Path>) -> Solution {
+        self.distribute_ants(paths, self.ant_count)
+    }
+
+    /// Same as [`Graph::distribute`] for `ant_count` ants
+    fn distribute_ants(&self, paths: Vec<Path>, ant_count: usize) -> Solution {
+        let durations: Vec<_> = paths.iter().map(|path| path.duration(self)).collect();
 
         let mut steps = Vec::new();
-        let mut remaining_ants = self.ant_count;
+        let mut remaining_ants = ant_count;
         let mut used_path = &paths[..];
-        while let Some((longest, others)) = used_path.split_last() {
-            let min_required_ants: usize = others.iter().map(|p| longest.len() - p.len()).sum();
-            if let Some(rest) = remaining_ants.checked_sub(min_required_ants) {
+        while let Some((_, others)) = used_path.split_last() {
+            let longest = durations[others.len()];
+            let min_required_ants = durations[..others.len()]
+                .iter()
+                .try_fold(0usize, |sum, duration| sum.checked_add(longest - duration));
+            let rest = min_required_ants.and_then(|required| remaining_ants.checked_sub(required));
+            if let Some(rest) = rest {
                 let duration = rest / used_path.len();
                 if duration != 0 {
                     steps.push(Step {
@@ -134,6 +345,271 @@ impl Graph {
             }
             used_path = others;
         }
-        Some(Solution(steps))
+        let mut solution = Solution {
+            steps,
+            turn_count: 0,
+            lower_bound: self.turn_lower_bound_for(ant_count),
+            cut_short: false,
+        };
+        solution.turn_count = if self.flows_freely(&paths) {
+            // The last ant of each path leaves on the turn it gets, so that
+            // the turn count does not depend on replaying every ant
+            let ants = solution.ants_per_path();
+            durations
+                .iter()
+                .zip(ants)
+                .filter(|&(_, ants)| ants != 0)
+                .map(|(&duration, ants)| (ants - 1).saturating_add(duration))
+                .max()
+                .unwrap_or(0)
+        } else {
+            // Replayed, as paths sharing a room too much hold ants back
+            solution.simulate(self).count()
+        };
+        // The bound is not tight. The curve is exact instead: sending ants
+        // along the paths of a min-cost flow every turn is a quickest flow
+        // (Ford & Fulkerson's temporally repeated flows, see Burkard et
+        // al., "The quickest flow problem"), as many ants as a max flow over
+        // the rooms at every turn lets through, see [`Graph::min_turns`]
+        // It does not depend on the paths distributed above, so a solver
+        // only matches it with as few turns as can be
+        if solution.lower_bound.is_some_and(|bound| bound < solution.turn_count) {
+            let flow = MinCostFlow::new(self);
+            solution.lower_bound = TurnCurve::min_turns_from(&[], flow, ant_count, None);
+        }
+        solution
+    }
+
+    /// Whether ants sent one per turn along each of `paths` never wait for
+    /// one another: no room is on more paths than it holds ants, & no
+    /// tunnel is on two paths when tunnels are exclusive
+    fn flows_freely(&self, paths: &[Path]) -> bool {
+        let mut load = vec![0; self.nodes.len()];
+        let mut tunnels = HashSet::new();
+        for path in paths {
+            let mut from = self.start;
+            for &to in path.as_ref() {
+                let load = &mut load[usize::from(to)];
+                *load += 1;
+                if to != self.end && *load > self[to].capacity {
+                    return false;
+                }
+                let instant = self.link_length(from, to) == Some(0);
+                let tunnel = (from.min(to), from.max(to));
+                if self.routing == Routing::EdgeDisjoint && !instant && !tunnels.insert(tunnel) {
+                    return false;
+                }
+                from = to;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{CancelToken, Graph, ParseOptions, Progress, Routing, SolveOptions};
+
+    #[test]
+    fn deadline_keeps_the_first_solution() {
+        let graph: Graph = include_str!("../../maps/handmade/three_route").parse().unwrap();
+        let options = SolveOptions {
+            deadline: Some(std::time::Instant::now()),
+            ..SolveOptions::default()
+        };
+        let solution = graph.solve_with(&options).unwrap();
+
+        assert!(solution.is_cut_short());
+        assert_eq!(solution.paths().len(), 1);
+        assert!(!graph.solve().unwrap().is_cut_short());
+    }
+
+    #[test]
+    fn progress_reports_and_cancellation() {
+        let graph: Graph = include_str!("../../maps/handmade/three_route").parse().unwrap();
+        let cancel = CancelToken::new();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let options = SolveOptions {
+            cancel: Some(cancel.clone()),
+            progress: Some(Arc::new({
+                let reports = Arc::clone(&reports);
+                let cancel = cancel.clone();
+                move |progress: &Progress| {
+                    reports.lock().unwrap().push(progress.clone());
+                    if progress.valid_paths == 2 {
+                        cancel.cancel();
+                    }
+                }
+            })),
+            ..SolveOptions::default()
+        };
+        let solution = graph.solve_with(&options).unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[1].branches_explored, 2);
+        assert_eq!(reports[1].best_turns, Some(solution.turn_count()));
+        assert!(solution.is_cut_short());
+        assert!(graph.solve_with(&options).is_none());
+    }
+
+    #[test]
+    fn handmade_maps_are_solved_in_min_turns() {
+        let maps = [
+            include_str!("../../maps/handmade/subject_map"),
+            include_str!("../../maps/handmade/bottleneck"),
+            include_str!("../../maps/handmade/three_route"),
+            include_str!("../../maps/handmade/duration"),
+            include_str!("../../maps/handmade/multiple_stage_transfer"),
+        ];
+        for map in maps {
+            let graph: Graph = map.parse().unwrap();
+            let solution = graph.solve().unwrap();
+            let turns = solution.to_schedule(&graph).turn_count();
+            assert_eq!(Some(turns), graph.min_turns());
+            assert_eq!(turns, solution.turn_count());
+            assert!(solution.is_optimal());
+        }
+    }
+
+    #[test]
+    fn huge_ant_counts_are_solved_in_closed_form() {
+        let graph: Graph = "4000000000\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a\na-e\ns-b\nb-c\nc-e"
+            .parse()
+            .unwrap();
+        let solution = graph.solve().unwrap();
+
+        assert_eq!(solution.ants_per_path(), [2_000_000_001, 1_999_999_999]);
+        assert_eq!(solution.turn_count(), 2_000_000_002);
+        let mut output = Vec::new();
+        solution.write_summary(&graph, &mut output).unwrap();
+        let summary = String::from_utf8(output).unwrap();
+        assert!(summary.starts_with("4000000000 ants in 2000000002 turns\n"));
+
+        let big: Graph = include_str!("../../maps/generated/big").parse().unwrap();
+        let solution = big.solve().unwrap();
+        assert_eq!(solution.turn_count(), solution.simulate(&big).count());
+    }
+
+    #[test]
+    fn most_ants_within_a_turn_limit() {
+        let graph: Graph = "1\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a\na-e\ns-b\nb-c\nc-e"
+            .parse()
+            .unwrap();
+        assert!(graph.solve_within(1).is_none());
+        let solution = graph.solve_within(4).unwrap();
+        assert_eq!(solution.ants_per_path(), [3, 2]);
+        assert_eq!(solution.turn_count(), 4);
+        assert_eq!(solution.simulate(&graph).count(), 4);
+
+        let subject: Graph = include_str!("../../maps/handmade/subject_map").parse().unwrap();
+        let curve = subject.turn_curve();
+        for turns in 0..20 {
+            let ants = subject.solve_within(turns).map_or(0, |solution| {
+                assert!(solution.turn_count() <= turns);
+                solution.ant_count()
+            });
+            assert_eq!(ants, curve.max_ants(turns));
+        }
+    }
+
+    #[test]
+    fn shared_room_respects_capacity() {
+        let graph: Graph = "4\n##start\ns 0 0\nx 0 0\ny 0 0\n##capacity 2\na 0 0\n##end\ne 0 0\n\
+            s-x\ns-y\nx-a\ny-a\na-e"
+            .parse()
+            .unwrap();
+        let solution = graph.solve().unwrap();
+        let a = graph.find("a").unwrap();
+
+        let mut in_a = 0;
+        let mut turns = 0;
+        for moves in solution.simulate(&graph) {
+            for m in moves {
+                match m.node {
+                    id if id == a => in_a += 1,
+                    _ if m.node == graph.end() => in_a -= 1,
+                    _ => {}
+                }
+            }
+            assert!(in_a <= 2);
+            turns += 1;
+        }
+        assert_eq!(solution.paths().len(), 2);
+        assert_eq!(turns, 4);
+    }
+
+    #[test]
+    fn edge_disjoint_paths_share_rooms() {
+        let map = "4\n##start\ns 0 0\nx 0 0\ny 0 0\nb 0 0\nz 0 0\nw 0 0\n##end\ne 0 0\n\
+            s-x\ns-y\nx-b\ny-b\nb-z\nb-w\nz-e\nw-e";
+        let options = ParseOptions {
+            routing: Routing::EdgeDisjoint,
+            ..ParseOptions::default()
+        };
+        let vertex: Graph = map.parse().unwrap();
+        let edge = Graph::parse_with(map, &options).unwrap();
+
+        assert_eq!(vertex.solve().unwrap().turn_count(), 7);
+        let solution = edge.solve().unwrap();
+        assert_eq!(solution.paths().len(), 2);
+        assert_eq!(solution.turn_count(), 5);
+
+        let mut output = Vec::new();
+        solution.write_to(&edge, &mut output).unwrap();
+        let transcript = std::str::from_utf8(&output).unwrap();
+        assert_eq!(edge.check(transcript).unwrap(), 5);
+        assert!(vertex.check(transcript).is_err());
+    }
+
+    #[test]
+    fn one_way_tunnels_are_not_taken_backward() {
+        // The only short route would go through b>a backward
+        let graph: Graph = "2\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\nd 0 0\n##end\ne 0 0\n\
+            s-a\nb>a\nb-e\na-c\nc-d\nd-e"
+            .parse()
+            .unwrap();
+        let solution = graph.solve().unwrap();
+        let names: Vec<_> = solution.paths()[0]
+            .as_ref()
+            .iter()
+            .map(|&id| graph[id].name.as_ref())
+            .collect();
+
+        assert_eq!(names, ["a", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn several_start_and_end_rooms() {
+        let map = "4\n##start\ns1 0 0\n##start\ns2 0 0\na 0 0\nb 0 0\n\
+            ##end\ne1 0 0\n##end\ne2 0 0\ns1-a\na-e1\ns2-b\nb-e2";
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let graph = Graph::parse_with(map, &options).unwrap();
+        let solution = graph.solve().unwrap();
+        let mut output = Vec::new();
+        solution.write_to(&graph, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "L1-a L2-b\nL1-e1 L2-e2 L3-a L4-b\nL3-e1 L4-e2\n",
+        );
+    }
+
+    #[test]
+    fn long_tunnels_are_travelled_without_moves() {
+        let graph: Graph = "5\n##start\ns 0 0\na 0 0\nb 0 0\n##end\ne 0 0\n\
+            s-a 3\na-e\ns-b\nb-e"
+            .parse()
+            .unwrap();
+        let solution = graph.solve().unwrap();
+        let turns: Vec<_> = solution.simulate(&graph).map(|moves| moves.len()).collect();
+
+        // The first ant spends 3 turns in the s-a tunnel & reaches `a` on turn 3
+        assert_eq!(solution.paths().len(), 2);
+        assert_eq!(turns, [1, 2, 3, 3, 1]);
     }
 }
@@ -0,0 +1,324 @@
+use std::{collections::VecDeque, io};
+
+use super::{Graph, NodeId};
+
+/// Structural summary of a map, meant to triage it before solving
+#[derive(Debug)]
+pub struct Stats {
+    pub room_count: usize,
+    pub link_count: usize,
+    /// Number of rooms for each degree, indexed by degree
+    /// The degree is the number of rooms linked to, whatever the direction
+    pub degree_distribution: Vec<usize>,
+    pub start_degree: usize,
+    pub end_degree: usize,
+    /// Length of the shortest path from start to end, if any
+    pub distance: Option<usize>,
+    pub component_count: usize,
+    /// Lower bound of the diameter of the start component (double sweep)
+    pub diameter_estimate: usize,
+    pub articulation_points: Vec<NodeId>,
+    pub max_disjoint_paths: usize,
+    /// Rooms limiting the throughput, see [`Graph::min_vertex_cut`]
+    pub min_vertex_cut: Vec<NodeId>,
+}
+
+/// Breadth first search distances from the closest of `from`
+fn bfs(adjacency: &[Vec<usize>], from: &[usize]) -> Vec<Option<usize>> {
+    let mut distances = vec![None; adjacency.len()];
+    for &from in from {
+        distances[from] = Some(0);
+    }
+    let mut queue = VecDeque::from(from.to_vec());
+    while let Some(id) = queue.pop_front() {
+        let next = distances[id].map(|d| d + 1);
+        for &link in &adjacency[id] {
+            if distances[link].is_none() {
+                distances[link] = next;
+                queue.push_back(link);
+            }
+        }
+    }
+    distances
+}
+
+fn farthest(distances: &[Option<usize>]) -> (usize, usize) {
+    distances
+        .iter()
+        .enumerate()
+        .filter_map(|(id, d)| d.map(|d| (id, d)))
+        .max_by_key(|&(_, d)| d)
+        .unwrap_or((0, 0))
+}
+
+/// Returns the number of connected components & the articulation points
+/// of the `rooms` (iterative Tarjan so deep maps do not overflow the stack)
+fn components_and_articulation_points(
+    adjacency: &[Vec<usize>],
+    rooms: &[bool],
+) -> (usize, Vec<NodeId>) {
+    const UNVISITED: usize = usize::MAX;
+    let mut discovery = vec![UNVISITED; adjacency.len()];
+    let mut low = vec![0; adjacency.len()];
+    let mut is_articulation = vec![false; adjacency.len()];
+    let mut timer = 0;
+    let mut component_count = 0;
+
+    for root in 0..adjacency.len() {
+        if discovery[root] != UNVISITED || !rooms[root] {
+            continue;
+        }
+        component_count += 1;
+        discovery[root] = timer;
+        low[root] = timer;
+        timer += 1;
+        let mut root_children = 0;
+        // (node, parent, index of the next neighbour to visit)
+        let mut stack = vec![(root, UNVISITED, 0)];
+        while let Some(&(id, parent, next)) = stack.last() {
+            if let Some(&link) = adjacency[id].get(next) {
+                stack.last_mut().unwrap().2 += 1;
+                if discovery[link] == UNVISITED {
+                    discovery[link] = timer;
+                    low[link] = timer;
+                    timer += 1;
+                    if id == root {
+                        root_children += 1;
+                    }
+                    stack.push((link, id, 0));
+                } else if link != parent {
+                    low[id] = low[id].min(discovery[link]);
+                }
+                continue;
+            }
+            stack.pop();
+            if parent != UNVISITED {
+                low[parent] = low[parent].min(low[id]);
+                if parent != root && low[id] >= discovery[parent] {
+                    is_articulation[parent] = true;
+                }
+            }
+        }
+        is_articulation[root] = root_children > 1;
+    }
+
+    let articulation_points = is_articulation
+        .iter()
+        .enumerate()
+        .filter(|(_, &is)| is)
+        .map(|(id, _)| NodeId::from(id))
+        .collect();
+    (component_count, articulation_points)
+}
+
+impl Graph {
+    /// Tunnels of the map, without the virtual ones joining several start
+    /// or end rooms
+    fn tunnels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.nodes.iter().enumerate().flat_map(move |(id, node)| {
+            node.links
+                .iter()
+                .map(move |link| (id, usize::from(link.to)))
+                .filter(|&(from, to)| self.is_room(from.into()) && self.is_room(to.into()))
+        })
+    }
+
+    fn directed_adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.nodes.len()];
+        for (from, to) in self.tunnels() {
+            adjacency[from].push(to);
+        }
+        adjacency
+    }
+
+    /// Adjacency without direction, self links nor duplicates
+    fn undirected_adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.nodes.len()];
+        for (id, link) in self.tunnels().filter(|&(id, link)| id != link) {
+            adjacency[id].push(link);
+            adjacency[link].push(id);
+        }
+        for links in &mut adjacency {
+            links.sort_unstable();
+            links.dedup();
+        }
+        adjacency
+    }
+
+    /// Structure of the rooms of the map, the virtual ones joining several
+    /// start or end rooms aside
+    pub fn stats(&self) -> Stats {
+        let undirected = self.undirected_adjacency();
+        let rooms: Vec<_> = (0..self.nodes.len()).map(|id| self.is_room(id.into())).collect();
+        let starts: Vec<_> = self.starts.iter().map(|&id| usize::from(id)).collect();
+        let ends: Vec<_> = self.ends.iter().map(|&id| usize::from(id)).collect();
+        let degree = |rooms: &[usize]| rooms.iter().map(|&id| undirected[id].len()).sum();
+
+        let mut degree_distribution = Vec::new();
+        for links in (0..undirected.len()).filter(|&id| rooms[id]).map(|id| &undirected[id]) {
+            let degree = links.len();
+            if degree_distribution.len() <= degree {
+                degree_distribution.resize(degree + 1, 0);
+            }
+            degree_distribution[degree] += 1;
+        }
+
+        let from_starts = bfs(&self.directed_adjacency(), &starts);
+        let distance = ends.iter().filter_map(|&id| from_starts[id]).min();
+
+        let (sweep_origin, _) = farthest(&bfs(&undirected, &starts));
+        let (_, diameter_estimate) = farthest(&bfs(&undirected, &[sweep_origin]));
+
+        let (component_count, articulation_points) =
+            components_and_articulation_points(&undirected, &rooms);
+
+        Stats {
+            room_count: rooms.iter().filter(|&&room| room).count(),
+            link_count: undirected.iter().map(Vec::len).sum::<usize>() / 2,
+            degree_distribution,
+            start_degree: degree(&starts),
+            end_degree: degree(&ends),
+            distance,
+            component_count,
+            diameter_estimate,
+            articulation_points,
+            max_disjoint_paths: self.max_disjoint_paths(),
+            min_vertex_cut: self.min_vertex_cut(),
+        }
+    }
+}
+
+/// Escape a string to be embedded in a JSON document
+fn json_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+impl Stats {
+    fn degrees(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.degree_distribution
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, count)| count != 0)
+    }
+
+    pub fn write_human(&self, graph: &Graph, mut output: impl io::Write) -> io::Result<()> {
+        writeln!(output, "Rooms: {}", self.room_count)?;
+        writeln!(output, "Links: {}", self.link_count)?;
+        writeln!(output, "Degree distribution:")?;
+        for (degree, count) in self.degrees() {
+            writeln!(output, "    {degree}: {count} rooms")?;
+        }
+        writeln!(output, "Start degree: {}", self.start_degree)?;
+        writeln!(output, "End degree: {}", self.end_degree)?;
+        match self.distance {
+            Some(distance) => writeln!(output, "Distance start-end: {distance}")?,
+            None => writeln!(output, "Distance start-end: unreachable")?,
+        }
+        writeln!(output, "Components: {}", self.component_count)?;
+        writeln!(output, "Diameter (estimate): {}", self.diameter_estimate)?;
+        write!(output, "Articulation points ({}):", self.articulation_points.len())?;
+        for &id in &self.articulation_points {
+            write!(output, " {}", graph[id].name.as_ref())?;
+        }
+        writeln!(output)?;
+        writeln!(output, "Max disjoint paths: {}", self.max_disjoint_paths)?;
+        write!(output, "Bottleneck rooms ({}):", self.min_vertex_cut.len())?;
+        for &id in &self.min_vertex_cut {
+            write!(output, " {}", graph[id].name.as_ref())?;
+        }
+        writeln!(output)
+    }
+
+    pub fn write_json(&self, graph: &Graph, mut output: impl io::Write) -> io::Result<()> {
+        writeln!(output, "{{")?;
+        writeln!(output, "  \"rooms\": {},", self.room_count)?;
+        writeln!(output, "  \"links\": {},", self.link_count)?;
+        let degrees: Vec<_> = self
+            .degrees()
+            .map(|(degree, count)| format!("{{\"degree\": {degree}, \"rooms\": {count}}}"))
+            .collect();
+        writeln!(output, "  \"degree_distribution\": [{}],", degrees.join(", "))?;
+        writeln!(output, "  \"start_degree\": {},", self.start_degree)?;
+        writeln!(output, "  \"end_degree\": {},", self.end_degree)?;
+        match self.distance {
+            Some(distance) => writeln!(output, "  \"distance\": {distance},")?,
+            None => writeln!(output, "  \"distance\": null,")?,
+        }
+        writeln!(output, "  \"components\": {},", self.component_count)?;
+        writeln!(output, "  \"diameter_estimate\": {},", self.diameter_estimate)?;
+        let articulation_points: Vec<_> = self
+            .articulation_points
+            .iter()
+            .map(|&id| json_string(graph[id].name.as_ref()))
+            .collect();
+        writeln!(output, "  \"articulation_points\": [{}],", articulation_points.join(", "))?;
+        writeln!(output, "  \"max_disjoint_paths\": {},", self.max_disjoint_paths)?;
+        let min_vertex_cut: Vec<_> = self
+            .min_vertex_cut
+            .iter()
+            .map(|&id| json_string(graph[id].name.as_ref()))
+            .collect();
+        writeln!(output, "  \"min_vertex_cut\": [{}]", min_vertex_cut.join(", "))?;
+        writeln!(output, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Graph, ParseOptions};
+
+    #[test]
+    fn virtual_terminals_are_left_out() {
+        let map = "4\n##start\ns1 0 0\n##start\ns2 0 0\na 0 0\n##end\ne 0 0\ns1-a\ns2-a\na-e";
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let graph = Graph::parse_with(map, &options).unwrap();
+        let stats = graph.stats();
+
+        assert_eq!(stats.room_count, 4);
+        assert_eq!(stats.link_count, 3);
+        assert_eq!(stats.degree_distribution, [0, 3, 0, 1]);
+        assert_eq!(stats.start_degree, 2);
+        assert_eq!(stats.end_degree, 1);
+        assert_eq!(stats.distance, Some(2));
+        assert_eq!(stats.component_count, 1);
+        let names: Vec<_> = stats
+            .articulation_points
+            .iter()
+            .map(|&id| graph[id].name.as_ref())
+            .collect();
+        assert_eq!(names, ["a"]);
+        assert_eq!(stats.max_disjoint_paths, 1);
+    }
+
+    #[test]
+    fn bottleneck_stats() {
+        let graph: Graph = include_str!("../../maps/handmade/bottleneck").parse().unwrap();
+        let stats = graph.stats();
+
+        assert_eq!(stats.room_count, 6);
+        assert_eq!(stats.link_count, 7);
+        assert_eq!(stats.distance, Some(3));
+        assert_eq!(stats.component_count, 1);
+        assert_eq!(stats.diameter_estimate, 3);
+        let names: Vec<_> = stats
+            .articulation_points
+            .iter()
+            .map(|&id| graph[id].name.as_ref())
+            .collect();
+        assert_eq!(names, ["bottleneck"]);
+        assert_eq!(stats.max_disjoint_paths, 1);
+        assert_eq!(stats.min_vertex_cut, stats.articulation_points);
+    }
+}
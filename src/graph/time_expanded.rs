@@ -0,0 +1,155 @@
+use super::{
+    flow::{link_capacity, FlowNetwork},
+    Graph, NodeId, Progress, SolveOptions,
+};
+
+const UNBOUNDED: usize = usize::MAX;
+/// Ants waiting in start before the first turn
+const SOURCE: usize = 0;
+/// Every arrival in the end room flows there, whatever the turn
+const SINK: usize = 1;
+
+/// Flow network of the rooms at every turn up to `turns`
+/// An ant is a unit of flow: it waits in a room from one turn to the next or
+/// goes through a tunnel to the room it reaches some turns later. Rooms
+/// keep their capacity at every turn, closed rooms & tunnels are left out
+struct TimeExpanded<'a> {
+    graph: &'a Graph,
+    network: FlowNetwork,
+    turns: usize,
+}
+
+impl<'a> TimeExpanded<'a> {
+    fn new(graph: &'a Graph) -> Self {
+        let mut expanded = Self {
+            graph,
+            network: FlowNetwork::empty(SOURCE, SINK),
+            turns: 0,
+        };
+        expanded.add_layer(0);
+        let start = expanded.in_vertex(graph.start(), 0);
+        expanded.network.add_arc(SOURCE, start, UNBOUNDED);
+        expanded
+    }
+
+    fn in_vertex(&self, id: NodeId, turn: usize) -> usize {
+        2 + 2 * (turn * self.graph.nodes().len() + usize::from(id))
+    }
+
+    fn out_vertex(&self, id: NodeId, turn: usize) -> usize {
+        self.in_vertex(id, turn) + 1
+    }
+
+    /// Add the rooms at `turn` & the moves ending on that turn
+    fn add_layer(&mut self, turn: usize) {
+        let graph = self.graph;
+        let missing = self.out_vertex(NodeId::from(graph.nodes().len() - 1), turn) + 1
+            - self.network.vertex_count();
+        self.network.add_vertices(missing);
+
+        for (id, node) in graph.nodes().iter().enumerate() {
+            let id = NodeId::from(id);
+            let terminal = id == graph.start() || id == graph.end();
+            if id == graph.start() || !graph.is_room_closed(id, turn) {
+                let capacity = if terminal { UNBOUNDED } else { node.capacity };
+                self.network.add_arc(self.in_vertex(id, turn), self.out_vertex(id, turn), capacity);
+            }
+            if turn != 0 {
+                self.network.add_arc(self.out_vertex(id, turn - 1), self.in_vertex(id, turn), UNBOUNDED);
+            }
+            if id == graph.end() {
+                self.network.add_arc(self.in_vertex(id, turn), SINK, UNBOUNDED);
+            }
+            for &link in &node.links {
+                let Some(departure) = turn.checked_sub(link.length) else {
+                    continue;
+                };
+                if (departure + 1..=turn).any(|turn| graph.is_link_closed(id, link.to, turn)) {
+                    continue;
+                }
+                self.network.add_arc(
+                    self.out_vertex(id, departure),
+                    self.in_vertex(link.to, turn),
+                    link_capacity(graph, id, link),
+                );
+            }
+        }
+        self.turns = turn;
+    }
+}
+
+impl Graph {
+    /// Fewest turns needed to get every ant of the main colony to the end
+    /// Exact but slow: one max flow over the map repeated at every turn,
+    /// meant as a reference for small & medium maps. Ants crossing in a
+    /// tunnel are allowed as they can always swap roles instead
+    pub fn min_turns(&self) -> Option<usize> {
+        self.min_turns_with(&SolveOptions::default())
+    }
+
+    /// Same as [`Graph::min_turns`], `None` once past the deadline or
+    /// cancelled. Progress counts the turns tried as branches
+    pub fn min_turns_with(&self, options: &SolveOptions) -> Option<usize> {
+        if self.ant_count == 0 || self.start == self.end {
+            return Some(0);
+        }
+        if self.max_disjoint_paths() == 0 {
+            return None;
+        }
+        let mut expanded = TimeExpanded::new(self);
+        let mut flow = 0;
+        let mut progress = Progress::default();
+        loop {
+            if options.is_late() || options.is_cancelled() {
+                return None;
+            }
+            flow += expanded.network.max_flow(self.ant_count - flow);
+            if flow == self.ant_count {
+                return Some(expanded.turns);
+            }
+            expanded.add_layer(expanded.turns + 1);
+            progress.branches_explored = expanded.turns;
+            options.report(&progress);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Graph, ParseOptions};
+
+    #[test]
+    fn optimal_solutions_match_the_exact_turns() {
+        let maps = [
+            include_str!("../../maps/handmade/subject_map"),
+            include_str!("../../maps/handmade/duration"),
+            include_str!("../../maps/handmade/three_route"),
+            include_str!("../../maps/handmade/bottleneck"),
+            include_str!("../../maps/handmade/multiple_stage_transfer"),
+        ];
+        for map in maps {
+            let graph: Graph = map.parse().unwrap();
+            let solution = graph.solve().unwrap();
+
+            // The bound of the solution, from the min-cost flows, is checked
+            // against an independent search over the rooms at every turn
+            assert_eq!(solution.lower_bound(), graph.min_turns());
+            assert_eq!(solution.is_optimal(), graph.min_turns() == Some(solution.turn_count()));
+        }
+    }
+
+    #[test]
+    fn min_turns_of_small_maps() {
+        let line: Graph = "3\n##start\ns 0 0\na 0 0\n##end\ne 0 0\ns-a\na-e".parse().unwrap();
+        let wide: Graph = "3\n##start\ns 0 0\n##capacity 3\na 0 0\n##end\ne 0 0\ns-a\na-e"
+            .parse()
+            .unwrap();
+        let options = ParseOptions { strict: false, ..ParseOptions::default() };
+        let closed = "1\n##start\ns 0 0\na 0 0\n##end\ne 0 0\ns-a\na-e\n##closed a 1 4";
+        let closed = Graph::parse_with(closed, &options).unwrap();
+
+        assert_eq!(line.min_turns(), Some(4));
+        assert_eq!(wide.min_turns(), Some(2));
+        assert_eq!(closed.min_turns(), Some(6));
+    }
+}
@@ -0,0 +1,160 @@
+use super::{
+    curve::TurnCurve,
+    flow::{link_capacity, MinCostFlow},
+    Graph, Link, NodeId,
+};
+use crate::path::Distances;
+
+/// Tunnel that could be dug between two rooms, & the turns it would take
+/// the ants of the map to reach the end once it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkAddition {
+    pub a: NodeId,
+    pub b: NodeId,
+    pub turn_count: usize,
+}
+
+impl Graph {
+    /// Pairs of rooms at most `max_distance` apart & not linked yet
+    fn link_candidates(&self, max_distance: usize) -> Vec<(NodeId, NodeId)> {
+        let rooms: Vec<_> = (0..self.nodes.len())
+            .map(NodeId::from)
+            .filter(|&id| self.is_room(id))
+            .collect();
+        let max_distance = max_distance as u128 * max_distance as u128;
+        let mut candidates = Vec::new();
+        for (index, &a) in rooms.iter().enumerate() {
+            for &b in &rooms[index + 1..] {
+                let (pa, pb) = (&self[a].pos, &self[b].pos);
+                let (dx, dy) = (pa.x.abs_diff(pb.x) as u128, pa.y.abs_diff(pb.y) as u128);
+                let linked = self.link_length(a, b).is_some() || self.link_length(b, a).is_some();
+                if dx * dx + dy * dy <= max_distance && !linked {
+                    candidates.push((a, b));
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Most paths a new tunnel between `a` & `b` can be on at once
+    fn paths_through(&self, a: NodeId, b: NodeId) -> usize {
+        let capacity = |id: NodeId| {
+            if id == self.start || id == self.end { usize::MAX } else { self[id].capacity }
+        };
+        let tunnel = link_capacity(self, a, Link { to: b, length: 1 })
+            .saturating_add(link_capacity(self, b, Link { to: a, length: 1 }));
+        capacity(a).min(capacity(b)).min(tunnel)
+    }
+
+    /// Single new tunnel, one turn long & between rooms at most
+    /// `max_distance` apart, saving the most turns, `None` when none saves
+    /// any
+    /// The min-cost flows of the map are computed once, each candidate
+    /// resumes from the largest of them still of min cost with the new
+    /// tunnel. Candidates are tried by how quick a path along them could be
+    /// & left out when the paths along them could not make up for the ants
+    /// missing to save a turn
+    pub fn best_link_addition(&self, max_distance: usize) -> Option<LinkAddition> {
+        if self.start == self.end || self.ant_count == 0 {
+            return None;
+        }
+        let distances = Distances::new(self);
+        let through = |from: NodeId, to: NodeId| {
+            let (from, to) = (distances.from_start(from), distances.to_end(to));
+            from.zip(to).map_or(usize::MAX, |(from, to)| from + 1 + to)
+        };
+        let mut candidates: Vec<_> = self
+            .link_candidates(max_distance)
+            .into_iter()
+            .map(|(a, b)| (through(a, b).min(through(b, a)), a, b))
+            .collect();
+        candidates.sort_unstable();
+
+        let mut snapshots = Vec::new();
+        let flow = MinCostFlow::new(self);
+        let base = TurnCurve::min_turns_from(&[], flow, self.ant_count, Some(&mut snapshots));
+        // Paths costing as many turns as the base bring no ant sooner
+        let curve = TurnCurve::from_snapshots(&snapshots);
+        let mut best = base.unwrap_or(usize::MAX);
+        let mut addition = None;
+        for (duration, a, b) in candidates {
+            // Paths of a quicker solution take fewer turns than `best`: the
+            // ones along the new tunnel bring at most `best - duration` ants
+            // each, the others no more than without it
+            if duration >= best {
+                break;
+            }
+            let missing = self.ant_count - curve.max_ants(best - 1);
+            if (self.paths_through(a, b) as u128) * ((best - duration) as u128) < missing as u128 {
+                continue;
+            }
+            // The flows before the tunnel has a negative reduced cost stay
+            // of min cost, the first one always does
+            let resumed = snapshots.iter().take_while(|flow| flow.admits_link(a, b, 1)).count() - 1;
+            let mut flow = snapshots[resumed].clone();
+            flow.add_link(self, a, Link { to: b, length: 1 });
+            flow.add_link(self, b, Link { to: a, length: 1 });
+            let costs = &curve.costs()[..resumed];
+            let turns = TurnCurve::min_turns_from(costs, flow, self.ant_count, None);
+            if let Some(turns) = turns.filter(|&turns| turns < best) {
+                best = turns;
+                addition = Some(LinkAddition { a, b, turn_count: turns });
+            }
+        }
+        addition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Graph;
+
+    #[test]
+    fn shortcut_is_the_best_addition() {
+        let graph: Graph = "3\n##start\ns 0 0\na 0 2\nb 0 4\nc 0 6\nd 2 6\n##end\ne 2 4\n\
+            s-a\na-b\nb-c\nc-d\nd-e"
+            .parse()
+            .unwrap();
+        let name = |id| graph[id].name.as_ref();
+        assert_eq!(graph.solve().unwrap().turn_count(), 7);
+
+        let addition = graph.best_link_addition(2).unwrap();
+        assert_eq!((name(addition.a), name(addition.b)), ("b", "e"));
+        assert_eq!(addition.turn_count, 5);
+        assert!(graph.best_link_addition(1).is_none());
+
+        let map = include_str!("../../maps/handmade/subject_map");
+        let subject: Graph = map.parse().unwrap();
+        let addition = subject.best_link_addition(usize::MAX).unwrap();
+        let (a, b) = (subject[addition.a].name.as_ref(), subject[addition.b].name.as_ref());
+        let linked: Graph = format!("{map}\n{a}-{b}").parse().unwrap();
+        assert_eq!(linked.solve().unwrap().turn_count(), addition.turn_count);
+        assert!(addition.turn_count < subject.solve().unwrap().turn_count());
+    }
+
+    #[test]
+    fn resumed_searches_match_searches_from_scratch() {
+        let maps = [
+            include_str!("../../maps/handmade/subject_map"),
+            include_str!("../../maps/handmade/three_route"),
+            include_str!("../../maps/handmade/duration"),
+            include_str!("../../maps/handmade/multiple_stage_transfer"),
+        ];
+        for map in maps {
+            let graph: Graph = map.parse().unwrap();
+            let turns = |graph: &Graph| graph.turn_curve().min_turns(graph.ant_count()).unwrap();
+            let expected = graph
+                .link_candidates(usize::MAX)
+                .into_iter()
+                .map(|(a, b)| {
+                    let (a, b) = (graph[a].name.as_ref(), graph[b].name.as_ref());
+                    turns(&format!("{}\n{a}-{b}", map.trim_end()).parse().unwrap())
+                })
+                .min()
+                .filter(|&min| min < turns(&graph));
+
+            let addition = graph.best_link_addition(usize::MAX);
+            assert_eq!(addition.map(|addition| addition.turn_count), expected);
+        }
+    }
+}
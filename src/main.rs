@@ -1,6 +1,7 @@
 use std::io::Read;
 use std::fs::File;
-use lem_in::graph::Graph;
+use std::time::{Duration, Instant};
+use lem_in::graph::{Graph, ParseOptions, Releases, Routing, Solution, SolveOptions};
 
 const RANDOM_GRAPH_NODE_COUNT: usize = 4_000;
 const RANDOM_GRAPH_DENSITY: f32 = 0.001;
@@ -17,53 +18,329 @@ fn random_graph() -> Graph {
     )
 }
 
-fn show_graph_stats(graph: &Graph) {
-    println!("Start: {}", graph.start());
-    println!("End: {}", graph.end());
-    println!("Ant count: {}", graph.ant_count());
+#[derive(Default)]
+struct Options {
+    /// Map file, `--random` or stdin when `None`
+    source: Option<String>,
+    parse: ParseOptions,
+    json: bool,
+    verbose: bool,
+    /// Print the paths & the ant split rather than every move
+    summary: bool,
+    /// File with the turn each ant becomes available
+    releases: Option<String>,
+    /// Send as many ants as reach the end within this many turns
+    turns: Option<usize>,
+    /// Farthest apart two rooms may be to consider digging a tunnel
+    max_distance: Option<usize>,
+    /// Seconds given to the solver
+    time_limit: Option<f64>,
+    /// Race every strategy instead of using the min-cost flow alone
+    portfolio: bool,
 }
 
-fn load_graph(mut input: impl Read) -> Result<Graph, String> {
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("Missing value for {arg}"));
+            match arg.as_str() {
+                "--json" => options.json = true,
+                "--verbose" | "-v" => options.verbose = true,
+                "--summary" => options.summary = true,
+                "--non-strict" => options.parse.strict = false,
+                "--releases" => options.releases = Some(value()?),
+                "--routing" => match value()?.as_str() {
+                    "vertex" => options.parse.routing = Routing::VertexDisjoint,
+                    "edge" => options.parse.routing = Routing::EdgeDisjoint,
+                    routing => return Err(format!("Unknown routing {routing}")),
+                },
+                "--solver" => match value()?.as_str() {
+                    "min-cost" => options.portfolio = false,
+                    "portfolio" => options.portfolio = true,
+                    solver => return Err(format!("Unknown solver {solver}")),
+                },
+                "--turns" => {
+                    let turns = value()?;
+                    let turns = turns.parse().map_err(|_| format!("Invalid turns {turns}"))?;
+                    options.turns = Some(turns);
+                }
+                "--max-distance" => {
+                    let distance = value()?;
+                    let distance =
+                        distance.parse().map_err(|_| format!("Invalid distance {distance}"))?;
+                    options.max_distance = Some(distance);
+                }
+                "--time-limit" => {
+                    let limit = value()?;
+                    let limit = limit
+                        .parse()
+                        .ok()
+                        .filter(|limit: &f64| limit.is_finite() && *limit >= 0.0)
+                        .ok_or(format!("Invalid time limit {limit}"))?;
+                    options.time_limit = Some(limit);
+                }
+                _ if options.source.is_none() => options.source = Some(arg),
+                _ => return Err(format!("Unexpected argument {arg}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn explain_throughput(graph: &Graph) {
+    if graph.routing() == Routing::EdgeDisjoint {
+        eprintln!("Throughput limited to {} paths by tunnels", graph.throughput_majorant());
+        return;
+    }
+    let cut = graph.min_vertex_cut();
+    let names: Vec<_> = cut.iter().map(|&id| graph[id].name.as_ref()).collect();
+    eprintln!("Throughput limited to {} paths by rooms: {}",
+        graph.throughput_majorant(),
+        names.join(" "),
+    );
+}
+
+fn explain_gap(solution: &Solution) {
+    let turns = solution.turn_count();
+    match solution.lower_bound() {
+        Some(_) if solution.is_optimal() => eprintln!("{turns} turns, optimal"),
+        Some(bound) => eprintln!("{turns} turns, at least {bound} needed (gap {})", turns - bound),
+        None => eprintln!("{turns} turns, no lower bound"),
+    }
+}
+
+fn load_graph(mut input: impl Read, options: &ParseOptions) -> Result<Graph, String> {
     let mut content = String::new();
     input.read_to_string(&mut content)
         .map_err(|e| format!("Error reading file: {e}"))?;
-    content.parse()
+    Graph::parse_with(&content, options)
         .map_err(|e| format!("Invalid map: {e}"))
 }
 
-fn get_graph() -> Result<Graph, String> {
-    let arg = std::env::args().nth(1);
-    match arg.as_deref() {
-        Some(arg) if arg == "--random" => {
+fn get_graph(options: &Options) -> Result<Graph, String> {
+    match options.source.as_deref() {
+        Some("--random") => {
             eprintln!("Generating random map (dens = {}%)...",
                 RANDOM_GRAPH_DENSITY * 100.0
             );
             let graph = random_graph();
-            show_graph_stats(&graph);
+            // Kept off the output, which is the solution
+            graph.stats().write_human(&graph, std::io::stderr().lock())
+                .map_err(|e| format!("Could not write stats: {e}"))?;
             Ok(graph)
         },
         Some(path) => {
             let file = File::open(path)
                 .map_err(|e| format!("Could not read file: {e}"))?;
             eprintln!("Loading file {path}...");
-            load_graph(file)
+            load_graph(file, &options.parse)
         }
         None => {
             eprintln!("Loading stdin...");
-            load_graph(std::io::stdin())
+            load_graph(std::io::stdin(), &options.parse)
         }
     }
 }
 
+fn load_releases(path: &str, graph: &Graph) -> Result<Releases, String> {
+    let releases: Releases = std::fs::read_to_string(path)
+        .map_err(|e| format!("Could not read file: {e}"))?
+        .parse()
+        .map_err(|e| format!("Invalid releases: {e}"))?;
+    if releases.len() > graph.ant_count() {
+        return Err(format!("{} releases for {} ants", releases.len(), graph.ant_count()));
+    }
+    Ok(releases)
+}
+
+fn stats(options: &Options) -> Result<(), String> {
+    let graph = get_graph(options)?;
+    let stats = graph.stats();
+    let output = std::io::stdout().lock();
+    let result = if options.json {
+        stats.write_json(&graph, output)
+    } else {
+        stats.write_human(&graph, output)
+    };
+    result.map_err(|e| format!("Could not write stats: {e}"))
+}
+
+/// Print the fewest turns for every ant count, as the turns where it bends
+fn curve(options: &Options) -> Result<(), String> {
+    let graph = get_graph(options)?;
+    let curve = graph.turn_curve();
+    let output = std::io::stdout().lock();
+    let result = if options.json {
+        curve.write_json(output)
+    } else {
+        curve.write_human(output)
+    };
+    result.map_err(|e| format!("Could not write curve: {e}"))
+}
+
+/// Find the tunnel to dig that saves the most turns
+fn what_if(options: &Options) -> Result<(), String> {
+    let max_distance = options
+        .max_distance
+        .ok_or("The distance between rooms must be given with --max-distance")?;
+    let graph = get_graph(options)?;
+    match graph.best_link_addition(max_distance) {
+        Some(addition) => {
+            let turns = graph.turn_curve().min_turns(graph.ant_count());
+            let turns = turns.map_or("unreachable".to_owned(), |turns| format!("{turns} turns"));
+            println!("Tunnel {}-{} brings {turns} down to {} turns",
+                graph[addition.a].name.as_ref(),
+                graph[addition.b].name.as_ref(),
+                addition.turn_count,
+            );
+        }
+        None => println!("No tunnel between rooms {max_distance} apart at most saves a turn"),
+    }
+    Ok(())
+}
+
+/// Print the turns lost without each room & tunnel of the paths
+fn sensitivity(options: &Options) -> Result<(), String> {
+    let graph = get_graph(options)?;
+    graph
+        .write_sensitivity(std::io::stdout().lock())
+        .map_err(|e| format!("Could not write sensitivity: {e}"))
+}
+
+/// Check a solution read from stdin against the map
+fn check(options: &Options) -> Result<(), String> {
+    if options.source.is_none() {
+        return Err("The map must be given as a file to check a solution".to_owned());
+    }
+    let graph = get_graph(options)?;
+    let mut transcript = String::new();
+    std::io::stdin()
+        .read_to_string(&mut transcript)
+        .map_err(|e| format!("Error reading solution: {e}"))?;
+    let turns = graph
+        .check(&transcript)
+        .map_err(|e| format!("Invalid solution: {e}"))?;
+    println!("Valid solution in {turns} turns");
+    Ok(())
+}
+
 fn run() -> Result<(), String> {
-    let graph = get_graph()?;
-    match graph.solve() {
-        Some(solution) => println!("Print solution here"),
+    let mut args = std::env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("stats") => {
+            args.next();
+            return stats(&Options::parse(args)?);
+        }
+        Some("curve") => {
+            args.next();
+            return curve(&Options::parse(args)?);
+        }
+        Some("what-if") => {
+            args.next();
+            return what_if(&Options::parse(args)?);
+        }
+        Some("sensitivity") => {
+            args.next();
+            return sensitivity(&Options::parse(args)?);
+        }
+        Some("check") => {
+            args.next();
+            return check(&Options::parse(args)?);
+        }
+        _ => {}
+    }
+    let options = Options::parse(args)?;
+    let other_solvers = options.releases.is_some() || options.portfolio;
+    if options.turns.is_some() && (other_solvers || options.time_limit.is_some()) {
+        return Err("--turns can not be combined with --releases, --solver portfolio nor \
+            --time-limit".to_owned());
+    }
+    let graph = get_graph(&options)?;
+    if options.turns.is_some() && (!graph.closures().is_empty() || !graph.colonies().is_empty()) {
+        return Err("--turns can not be combined with closures nor colonies".to_owned());
+    }
+    explain_throughput(&graph);
+    let solve_options = SolveOptions {
+        deadline: options
+            .time_limit
+            .map(|limit| Instant::now() + Duration::from_secs_f64(limit)),
+        ..SolveOptions::default()
+    };
+    if let Some(path) = &options.releases {
+        if !graph.closures().is_empty() || !graph.colonies().is_empty() {
+            return Err("Releases can not be combined with closures nor colonies".to_owned());
+        }
+        let releases = load_releases(path, &graph)?;
+        match graph.solve_with_releases(&releases, &solve_options) {
+            Some(schedule) => schedule
+                .write_to(&graph, std::io::stdout().lock())
+                .map_err(|e| format!("Could not write solution: {e}"))?,
+            None => println!("No solution was found"),
+        }
+        return Ok(());
+    }
+    if !graph.closures().is_empty() || !graph.colonies().is_empty() {
+        let schedule = if graph.closures().is_empty() {
+            graph.solve_colonies(&solve_options)
+        } else {
+            graph.solve_with_closures(&solve_options)
+        };
+        match schedule {
+            Some(schedule) => schedule
+                .write_to(&graph, std::io::stdout().lock())
+                .map_err(|e| format!("Could not write solution: {e}"))?,
+            None => println!("No solution was found"),
+        }
+        return Ok(());
+    }
+    if let Some(turns) = options.turns {
+        if graph.start() == graph.end() {
+            println!("Start is the end, any number of ants is there within {turns} turns");
+            return Ok(());
+        }
+        match graph.solve_within(turns) {
+            Some(solution) => {
+                eprintln!("{} ants reach the end within {turns} turns", solution.ant_count());
+                write_solution(&solution, &graph, &options)?
+            }
+            None => println!("No ant can reach the end within {turns} turns"),
+        }
+        return Ok(());
+    }
+    let solution = if options.portfolio {
+        graph.solve_portfolio(&solve_options).map(|(solution, strategy)| {
+            eprintln!("Best solution found by the {strategy} strategy");
+            solution
+        })
+    } else {
+        graph.solve_with(&solve_options)
+    };
+    match solution {
+        Some(solution) => {
+            if solution.is_cut_short() {
+                eprintln!("Time limit reached, a better solution may exist");
+            }
+            if options.verbose {
+                explain_gap(&solution);
+            }
+            write_solution(&solution, &graph, &options)?
+        }
         None => println!("No solution was found"),
     }
     Ok(())
 }
 
+fn write_solution(solution: &Solution, graph: &Graph, options: &Options) -> Result<(), String> {
+    let output = std::io::stdout().lock();
+    if options.summary {
+        solution.write_summary(graph, output)
+    } else {
+        solution.write_to(graph, output)
+    }
+    .map_err(|e| format!("Could not write solution: {e}"))
+}
+
 fn main() {
     if let Err(err) = run() {
         println!("Error:\n    {err}");
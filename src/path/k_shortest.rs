@@ -0,0 +1,168 @@
+use super::{Distances, Path};
+use crate::{Graph, NodeId};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+/// Quickest way from `from` to end avoiding some rooms & tunnels, with its
+/// duration & its rooms, `from` included
+/// Rooms are searched by the least turns a way through them could take
+/// (A*), the turns to end of `distances` leaving out rooms cut off from it
+fn quickest(
+    graph: &Graph,
+    distances: &Distances,
+    from: NodeId,
+    banned_rooms: &[bool],
+    banned_links: &HashSet<(NodeId, NodeId)>,
+) -> Option<(usize, Vec<NodeId>)> {
+    let to = graph.end();
+    let mut durations = vec![usize::MAX; graph.nodes().len()];
+    let mut parents = vec![None; graph.nodes().len()];
+    durations[usize::from(from)] = 0;
+    let mut queue = BinaryHeap::from([Reverse((distances.to_end(from)?, from))]);
+    while let Some(Reverse((least, id))) = queue.pop() {
+        let duration = least - distances.to_end(id).expect("rooms left reach end");
+        if id == to {
+            let mut rooms = vec![to];
+            let mut id = to;
+            while let Some(parent) = parents[usize::from(id)] {
+                rooms.push(parent);
+                id = parent;
+            }
+            rooms.reverse();
+            return Some((duration, rooms));
+        }
+        if duration > durations[usize::from(id)] {
+            continue;
+        }
+        for link in &graph[id].links {
+            if banned_rooms[usize::from(link.to)] || banned_links.contains(&(id, link.to)) {
+                continue;
+            }
+            let Some(rest) = distances.to_end(link.to) else {
+                continue;
+            };
+            let next = duration + link.length;
+            if next < durations[usize::from(link.to)] {
+                durations[usize::from(link.to)] = next;
+                parents[usize::from(link.to)] = Some(id);
+                queue.push(Reverse((next + rest, link.to)));
+            }
+        }
+    }
+    None
+}
+
+/// Turns needed to go through `rooms`, in order
+fn duration(graph: &Graph, rooms: &[NodeId]) -> usize {
+    rooms
+        .windows(2)
+        .map(|pair| graph.link_length(pair[0], pair[1]).expect("paths follow links"))
+        .sum()
+}
+
+impl Path {
+    /// The `k` quickest paths from start to end going through no room
+    /// twice, quickest first, not necessarily disjoint (Yen's algorithm)
+    /// Fewer paths are returned when there are not that many
+    pub fn k_shortest_simple(graph: &Graph, k: usize) -> Vec<Self> {
+        let start = graph.start();
+        let distances = Distances::new(graph);
+        let mut banned_rooms = vec![false; graph.nodes().len()];
+        let mut banned_links = HashSet::new();
+        // Rooms of the paths found, start included
+        let mut found: Vec<Vec<NodeId>> = Vec::new();
+        let mut candidates = BinaryHeap::new();
+        let mut known = HashSet::new();
+
+        if k == 0 {
+            return Vec::new();
+        }
+        let quickest_from = |from, banned_rooms: &[bool], banned_links: &HashSet<_>| {
+            quickest(graph, &distances, from, banned_rooms, banned_links)
+        };
+        if let Some((duration, rooms)) = quickest_from(start, &banned_rooms, &banned_links) {
+            known.insert(rooms.clone());
+            candidates.push(Reverse((duration, rooms)));
+        }
+        while found.len() < k {
+            let Some(Reverse((_, rooms))) = candidates.pop() else {
+                break;
+            };
+            // Paths leaving the new one at each of its rooms, the root
+            // before that room being kept as is
+            for spur in 0..rooms.len() - 1 {
+                let root = &rooms[..=spur];
+                for path in found.iter().chain([&rooms]) {
+                    if path.len() > spur + 1 && path[..=spur] == *root {
+                        banned_links.insert((path[spur], path[spur + 1]));
+                    }
+                }
+                for &id in &root[..spur] {
+                    banned_rooms[usize::from(id)] = true;
+                }
+                let spur_path = quickest_from(rooms[spur], &banned_rooms, &banned_links);
+                banned_links.clear();
+                for &id in &root[..spur] {
+                    banned_rooms[usize::from(id)] = false;
+                }
+
+                if let Some((_, spur_path)) = spur_path {
+                    let mut candidate = root[..spur].to_vec();
+                    candidate.extend(spur_path);
+                    if known.insert(candidate.clone()) {
+                        candidates.push(Reverse((duration(graph, &candidate), candidate)));
+                    }
+                }
+            }
+            found.push(rooms);
+        }
+        found.into_iter().map(|rooms| rooms.into_iter().skip(1).collect()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Path;
+    use crate::Graph;
+    use std::collections::HashSet;
+
+    #[test]
+    fn k_shortest_simple_paths_of_small_maps() {
+        let twin: Graph = "1\n##start\ns 0 0\na 0 0\nb 0 0\n##end\ne 0 0\ns-a\na-e 2\ns-b\nb-e"
+            .parse()
+            .unwrap();
+        let paths = Path::k_shortest_simple(&twin, 5);
+
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].duration(&twin), 2);
+        assert_eq!(paths[1].duration(&twin), 3);
+
+        let subject: Graph = include_str!("../../maps/handmade/subject_map").parse().unwrap();
+        let paths = Path::k_shortest_simple(&subject, 10);
+        assert_eq!(paths[0].duration(&subject), subject.shortest_duration().unwrap());
+        let durations: Vec<_> = paths.iter().map(|path| path.duration(&subject)).collect();
+        assert!(durations.is_sorted());
+        let distinct: HashSet<_> = paths.iter().map(|path| path.as_ref().to_vec()).collect();
+        assert_eq!(distinct.len(), paths.len());
+        for path in &paths {
+            let rooms: HashSet<_> = path.as_ref().iter().collect();
+            assert_eq!(rooms.len(), path.len());
+            assert!(!rooms.contains(&subject.start()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+    use super::Path;
+    use test::bench::Bencher;
+
+    #[bench]
+    fn k_shortest_10_simple_paths_for_big_map(b: &mut Bencher) {
+        let graph = include_str!("../../maps/generated/big").parse().unwrap();
+        b.iter(|| Path::k_shortest_simple(&graph, 10));
+    }
+}
@@ -1,8 +1,12 @@
 mod shortest;
 mod n_shortest;
+mod k_shortest;
 
-use crate::NodeId;
+pub use shortest::Distances;
 
+use crate::{Graph, NodeId};
+
+/// Rooms entered by an ant, from the first one after start up to end
 #[derive(Debug, Clone)]
 pub struct Path(Vec<NodeId>);
 
@@ -22,4 +26,17 @@ impl Path {
     pub fn len(&self) -> usize {
         self.0.len()
     }
+
+    /// Turns needed by an ant to go from start to end
+    pub fn duration(&self, graph: &Graph) -> usize {
+        let mut from = graph.start();
+        self.0
+            .iter()
+            .map(|&to| {
+                let length = graph.link_length(from, to).expect("path follows links");
+                from = to;
+                length
+            })
+            .sum()
+    }
 }
@@ -1,18 +1,15 @@
-use crate::{Graph, NodeId};
-use super::{AccessRecord, Branch};
+use crate::NodeId;
+use super::{branch_id::BranchId, Branches};
+
+/// Rooms of a branch from its last one back to the one after start
 pub struct Backtrace<'a> {
-    current: Branch,
-    graph: &'a Graph,
-    accesses: &'a [AccessRecord],
+    current: BranchId,
+    branches: &'a Branches,
 }
 
 impl<'a> Backtrace<'a> {
-    pub fn new(graph: &'a Graph, accesses: &'a [AccessRecord], current: Branch) -> Self {
-        Self {
-            current,
-            graph,
-            accesses,
-        }
+    pub fn new(branches: &'a Branches, current: BranchId) -> Self {
+        Self { current, branches }
     }
 }
 
@@ -20,12 +17,8 @@ impl<'a> Iterator for Backtrace<'a> {
     type Item = NodeId;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current.node == self.graph.start() {
-            return None;
-        }
-
-        let result = self.current.node;
-        self.current = Branch{ ..self.accesses[usize::from(result)][&self.current.id] };
-        Some(result)
+        let branch = &self.branches[self.current];
+        self.current = branch.parent?;
+        Some(branch.node)
     }
 }
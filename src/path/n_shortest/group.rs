@@ -0,0 +1,234 @@
+use std::{collections::HashSet, iter};
+
+use crate::{Graph, NodeId};
+
+const WORD: usize = u64::BITS as usize;
+/// Dead ends remembered at most, to bound the memory of the search
+const MEMO_LIMIT: usize = 1 << 16;
+
+/// Set of candidate indices, without trailing empty words so that equal sets
+/// compare equal whatever the number of candidates when they were made
+pub(super) type Bits = Vec<u64>;
+
+fn contains(bits: &[u64], index: usize) -> bool {
+    bits.get(index / WORD).is_some_and(|word| word >> (index % WORD) & 1 == 1)
+}
+
+pub(super) fn insert(bits: &mut Bits, index: usize) {
+    if bits.len() <= index / WORD {
+        bits.resize(index / WORD + 1, 0);
+    }
+    bits[index / WORD] |= 1 << (index % WORD);
+}
+
+fn remove(bits: &mut Bits, index: usize) {
+    if let Some(word) = bits.get_mut(index / WORD) {
+        *word &= !(1 << (index % WORD));
+    }
+    trim(bits);
+}
+
+fn trim(bits: &mut Bits) {
+    while bits.last() == Some(&0) {
+        bits.pop();
+    }
+}
+
+fn intersects(a: &[u64], b: &[u64]) -> bool {
+    a.iter().zip(b).any(|(a, b)| a & b != 0)
+}
+
+/// Whether every shared room of `path` still has room for one more path
+fn fits(graph: &Graph, load: &[usize], path: &[NodeId]) -> bool {
+    path.iter().all(|&id| load[usize::from(id)] < graph[id].capacity)
+}
+
+pub(super) fn charge(load: &mut [usize], path: &[NodeId]) {
+    path.iter().for_each(|&id| load[usize::from(id)] += 1);
+}
+
+pub(super) fn discharge(load: &mut [usize], path: &[NodeId]) {
+    path.iter().for_each(|&id| load[usize::from(id)] -= 1);
+}
+
+/// Paths reaching the end found so far, & which ones can not go together
+#[derive(Default)]
+pub(super) struct Candidates {
+    durations: Vec<usize>,
+    /// Rooms that can be shared with other paths, up to their capacity
+    shared_nodes: Vec<Vec<NodeId>>,
+    /// Candidates going through a same single ant room, both ways
+    conflicts: Vec<Bits>,
+    /// Candidates with shared rooms, whether they fit depends on the others
+    sharing: Bits,
+    /// Candidates by increasing duration
+    order: Vec<usize>,
+    /// Sets of candidates without shared rooms no group of some size can be
+    /// made of
+    dead_ends: HashSet<(Bits, usize)>,
+}
+
+impl Candidates {
+    pub(super) fn len(&self) -> usize {
+        self.durations.len()
+    }
+
+    /// Add a path, in conflict with the `conflicts` candidates
+    pub(super) fn push(&mut self, duration: usize, shared_nodes: Vec<NodeId>, conflicts: Bits) {
+        let index = self.len();
+        for other in (0..index).filter(|&other| contains(&conflicts, other)) {
+            insert(&mut self.conflicts[other], index);
+        }
+        if !shared_nodes.is_empty() {
+            insert(&mut self.sharing, index);
+        }
+        let rank = self.order.partition_point(|&other| self.durations[other] <= duration);
+        self.order.insert(rank, index);
+        self.durations.push(duration);
+        self.shared_nodes.push(shared_nodes);
+        self.conflicts.push(conflicts);
+    }
+
+    /// Group of `count` candidates of least total duration that go together
+    /// & with a new path, conflicting with `conflicts` & whose shared rooms
+    /// are already counted in `load`
+    pub(super) fn best_group(
+        &mut self,
+        graph: &Graph,
+        load: &mut [usize],
+        conflicts: &[u64],
+        count: usize,
+    ) -> Option<Vec<usize>> {
+        let mut allowed: Bits = vec![u64::MAX; self.len() / WORD];
+        if !self.len().is_multiple_of(WORD) {
+            allowed.push((1 << (self.len() % WORD)) - 1);
+        }
+        for (word, conflicts) in allowed.iter_mut().zip(conflicts) {
+            *word &= !conflicts;
+        }
+        trim(&mut allowed);
+
+        let mut search = Search {
+            graph,
+            candidates: self,
+            load,
+            chosen: Vec::with_capacity(count),
+            best: None,
+            dead_ends: HashSet::new(),
+            spare: Vec::new(),
+        };
+        search.explore(allowed, count, 0);
+        let Search { best, dead_ends, .. } = search;
+        if self.dead_ends.len() < MEMO_LIMIT {
+            self.dead_ends.extend(dead_ends);
+        }
+        best.map(|(_, group)| group)
+    }
+}
+
+/// Branch & bound over the groups of compatible candidates, shortest
+/// candidates first
+struct Search<'a> {
+    graph: &'a Graph,
+    candidates: &'a Candidates,
+    load: &'a mut [usize],
+    chosen: Vec<usize>,
+    /// Total duration & candidates of the best group found so far
+    best: Option<(usize, Vec<usize>)>,
+    /// Dead ends found by this search
+    dead_ends: HashSet<(Bits, usize)>,
+    /// Sets no longer used, to spare allocations
+    spare: Vec<Bits>,
+}
+
+impl<'a> Search<'a> {
+    fn buffer(&mut self) -> Bits {
+        let mut bits = self.spare.pop().unwrap_or_default();
+        bits.clear();
+        bits
+    }
+
+    /// Complete the chosen candidates with `count` candidates of `allowed`
+    /// Returns whether some group exists, `None` when bounding kept part of
+    /// the search from telling
+    fn explore(&mut self, mut allowed: Bits, count: usize, total: usize) -> Option<bool> {
+        let candidates: &'a Candidates = self.candidates;
+        if count == 0 {
+            if self.best.as_ref().is_none_or(|&(best, _)| total < best) {
+                self.best = Some((total, self.chosen.clone()));
+            }
+            self.spare.push(allowed);
+            return Some(true);
+        }
+        // Whether a group exists only depends on the set when no shared room
+        // is involved
+        let entry = if intersects(&allowed, &candidates.sharing) {
+            None
+        } else {
+            let key = (allowed, count);
+            if candidates.dead_ends.contains(&key) || self.dead_ends.contains(&key) {
+                self.spare.push(key.0);
+                return Some(false);
+            }
+            allowed = self.buffer();
+            allowed.extend_from_slice(&key.0);
+            Some(key)
+        };
+
+        let (mut found, mut exact) = (false, true);
+        for (rank, &path) in candidates.order.iter().enumerate() {
+            if !contains(&allowed, path) {
+                continue;
+            }
+            // Earlier candidates were already tried, the group can at best
+            // be completed with the shortest ones left
+            let (mut left, mut bound) = (0, total);
+            for &other in candidates.order[rank..]
+                .iter()
+                .filter(|&&other| contains(&allowed, other))
+                .take(count)
+            {
+                left += 1;
+                bound += candidates.durations[other];
+            }
+            if left < count {
+                break;
+            }
+            if self.best.as_ref().is_some_and(|&(best, _)| bound >= best) {
+                exact = false;
+                break;
+            }
+
+            remove(&mut allowed, path);
+            let shared_nodes = &candidates.shared_nodes[path];
+            if !fits(self.graph, self.load, shared_nodes) {
+                continue;
+            }
+            let mut next = self.buffer();
+            let conflicts = candidates.conflicts[path].iter().chain(iter::repeat(&0));
+            next.extend(allowed.iter().zip(conflicts).map(|(word, conflicts)| word & !conflicts));
+            trim(&mut next);
+
+            charge(self.load, shared_nodes);
+            self.chosen.push(path);
+            let result = self.explore(next, count - 1, total + candidates.durations[path]);
+            self.chosen.pop();
+            discharge(self.load, shared_nodes);
+            match result {
+                Some(true) => found = true,
+                Some(false) => {}
+                None => exact = false,
+            }
+        }
+        self.spare.push(allowed);
+
+        if found {
+            Some(true)
+        } else if exact {
+            self.dead_ends.extend(entry);
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
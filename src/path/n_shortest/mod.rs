@@ -1,187 +1,268 @@
 mod backtrace;
 mod branch_id;
+mod group;
 use backtrace::Backtrace;
+use group::{charge, discharge, Bits, Candidates};
 
-use super::Path;
+use super::{Distances, Path};
 
 use branch_id::BranchId;
-use crate::{BitArray, Graph, NodeId};
-use std::collections::{HashMap, VecDeque};
+use crate::{BitArray, Graph, NodeId, Progress, SolveOptions};
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    ops::{Index, IndexMut},
+};
+
+/// Branches explored between two progress reports
+const PROGRESS_PERIOD: usize = 1024;
 
 struct ValidPath {
-    branch: Branch,
+    branch: BranchId,
+    /// Rooms that can only hold one ant
     hit_node: BitArray,
-    incompats: BitArray,
 }
 
-struct BranchGenerator(BranchId);
-
-#[derive(Clone, Copy)]
-pub struct Branch { 
-    id : BranchId,
+/// A path from start explored by the search, stored as the last room & the
+/// branch it extends so branches share their common prefix
+struct Branch {
     node: NodeId,
+    /// Turns needed to go from start to `node` along the branch
+    duration: usize,
+    /// `None` for the branch standing on start
+    parent: Option<BranchId>,
+    /// Rooms of the path for constant time cycle checks, only kept while
+    /// the branch waits to be extended
+    visited: Option<BitArray>,
 }
 
-impl BranchGenerator {
-    fn new() -> Self {
-        Self(0.into())
+/// Every branch created so far, indexed by id
+#[derive(Default)]
+struct Branches(Vec<Branch>);
+
+impl Branches {
+    fn push(&mut self, branch: Branch) -> BranchId {
+        self.0.push(branch);
+        BranchId::from(self.0.len() - 1)
     }
+}
 
-    fn next(&mut self) -> BranchId {
-        let result = self.0;
-        self.0 = (usize::from(self.0) + 1).into();
-        result
+impl Index<BranchId> for Branches {
+    type Output = Branch;
+
+    fn index(&self, id: BranchId) -> &Self::Output {
+        &self.0[usize::from(id)]
     }
+}
 
-    pub fn create(&mut self, node: NodeId) -> Branch {
-        Branch {
-            id: self.next(),
-            node,
-        }
+impl IndexMut<BranchId> for Branches {
+    fn index_mut(&mut self, id: BranchId) -> &mut Self::Output {
+        &mut self.0[usize::from(id)]
     }
 }
 
+/// Branches to extend, by number of branches already reaching their room
+/// then by duration, so that long tunnels delay a branch as much as the
+/// rooms it would take as long to go through
 struct WorkQueue {
     max_overlap: usize,
-    queues : Vec<VecDeque<Branch>>,
+    queues: Vec<BinaryHeap<Reverse<(usize, BranchId)>>>,
 }
 
-type AccessRecord = HashMap<BranchId, Branch>;
-
 impl WorkQueue {
-    fn next(&mut self) -> Option<Branch> {
-        for queue in &mut self.queues {
-            match queue.pop_front() {
-                Some(branch) => return Some(branch),
-                None => {},
-            }
-        }
-        None
+    fn next(&mut self) -> Option<BranchId> {
+        self.queues
+            .iter_mut()
+            .find_map(BinaryHeap::pop)
+            .map(|Reverse((_, branch))| branch)
     }
 
     fn new(max_overlap: usize) -> Self {
         Self {
             max_overlap,
-            queues : Vec::new(),
+            queues: Vec::new(),
         }
     }
 
-    fn push(&mut self, branch: Branch, accesses: &[AccessRecord]) {
-        let access = &accesses[usize::from(branch.node)];
-        let i = access.len();
-        if i >= self.max_overlap {
-            return
-        }
-        match self.queues.get_mut(i) {
-            Some(queue) => queue.push_back(branch),
-            None => self.queues.push(VecDeque::from([branch])),
-        }
+    fn accepts(&self, overlap: usize) -> bool {
+        overlap < self.max_overlap
     }
-}
 
-fn find_group(
-    incompats: &BitArray,
-    paths: &[ValidPath],
-    start: usize,
-    count: usize,
-) -> Option<Vec<Branch>> {
-    if paths[start..].len() < count {
-        return None;
-    }
-    if count == 0 {
-        return Some(Vec::new());
-    }
-    for (path_index, path) in paths.iter().enumerate().skip(start) {
-        if incompats.get(path_index) {
-            continue;
-        }
-
-        let result = find_group(
-            &(incompats | &path.incompats),
-            paths,
-            path_index + 1,
-            count - 1,
-        );
-
-        if let Some(mut group) = result {
-            group.push(path.branch);
-            return Some(group);
+    fn push(&mut self, branch: BranchId, overlap: usize, duration: usize) {
+        if self.queues.len() <= overlap {
+            self.queues.resize_with(overlap + 1, BinaryHeap::new);
         }
+        self.queues[overlap].push(Reverse((duration, branch)));
     }
-    None
+}
+
+/// Path found by a branch, from start excluded to its last room
+fn path(branches: &Branches, branch: BranchId) -> Path {
+    let mut path: Vec<_> = Backtrace::new(branches, branch).collect();
+    path.reverse();
+    Path(path)
 }
 
 impl Path {
     // TODO: add find optimal
 
     // TODO: sort result Vec<_>
+    /// Find `n` paths that together respect the capacity of every room
     pub fn n_shortest(graph: &Graph, n: usize) -> Option<Vec<Self>> {
+        Self::n_shortest_with(graph, n, &SolveOptions::default())
+    }
+
+    /// Same as [`Path::n_shortest`], giving up once past the deadline or
+    /// cancelled & reporting its progress
+    pub fn n_shortest_with(graph: &Graph, n: usize, options: &SolveOptions) -> Option<Vec<Self>> {
         // TODO: find better way
         if n == 0 {
             return Some(Vec::new());
         }
-        // TODO: move to graph
-        let max_possible = graph.simple_throughput_majorant();
+        let max_possible = graph.throughput_majorant();
 
         if n > max_possible {
             return None;
         }
+        let node_count = graph.nodes().len();
+        // Rooms end can not be reached from are left out
+        let distances = Distances::new(graph);
         let mut work_queue = WorkQueue::new(2 * n);
-        let mut accesses: Vec<_> = (0..graph.nodes().len()).map(|_| AccessRecord::new()).collect();
+        let mut branches = Branches::default();
+        // Number of branches that reached each room
+        let mut accesses = vec![0; node_count];
         let mut valid_paths: Vec<ValidPath> = vec![];
+        let mut candidates = Candidates::default();
+        let mut load = vec![0; node_count];
 
-        let mut branch_generator = BranchGenerator::new();
-        let branch_origin = branch_generator.next();
-        // Here I put usize::MAX because should never be used
-        accesses[usize::from(graph.start())].insert(branch_origin, branch_generator.create(NodeId::from(usize::MAX)));
-        work_queue.push(branch_generator.create(graph.start()), &accesses);
+        let mut visited = BitArray::new(node_count);
+        visited.add(usize::from(graph.start()));
+        let origin = branches.push(Branch {
+            node: graph.start(),
+            duration: 0,
+            parent: None,
+            visited: Some(visited),
+        });
+        accesses[usize::from(graph.start())] += 1;
+        work_queue.push(origin, 1, 0);
 
+        let mut progress = Progress::default();
         let group = loop {
+            if options.is_late() || options.is_cancelled() {
+                return None;
+            }
+            progress.branches_explored += 1;
+            if progress.branches_explored % PROGRESS_PERIOD == 0 {
+                progress.valid_paths = valid_paths.len();
+                options.report(&progress);
+            }
             let branch = work_queue.next()?;
-            if branch.node == graph.end() {
-                let mut hit_node = BitArray::new(graph.nodes().len());
+            let visited = branches[branch].visited.take().expect("branches are extended once");
+            let node = branches[branch].node;
+            if node == graph.end() {
+                let mut hit_node = BitArray::new(node_count);
 
-                let mut incompats = BitArray::new(valid_paths.len());
-                for id in Backtrace::new(graph, &accesses, branch).skip(1) {
+                let mut shared_nodes = Vec::new();
+                let mut conflicts = Bits::new();
+                for id in Backtrace::new(&branches, branch).skip(1) {
+                    if graph[id].capacity > 1 {
+                        shared_nodes.push(id);
+                        continue;
+                    }
                     hit_node.add(usize::from(id));
                     for (path_index, path) in valid_paths.iter().enumerate() {
-                        incompats.add_if(path_index, path.hit_node.get(usize::from(id)));
+                        if path.hit_node.get(usize::from(id)) {
+                            group::insert(&mut conflicts, path_index);
+                        }
                     }
                 }
+                let duration = branches[branch].duration;
 
-                if let Some(mut group) = find_group(&incompats, &valid_paths, 0, n - 1) {
+                charge(&mut load, &shared_nodes);
+                let group = candidates.best_group(graph, &mut load, &conflicts, n - 1);
+                discharge(&mut load, &shared_nodes);
+                if let Some(group) = group {
+                    let mut group: Vec<_> =
+                        group.into_iter().map(|index| valid_paths[index].branch).collect();
                     group.push(branch);
                     break group;
                 }
 
-                valid_paths.push(ValidPath {
-                    branch,
-                    hit_node,
-                    incompats,
-                });
+                valid_paths.push(ValidPath { branch, hit_node });
+                candidates.push(duration, shared_nodes, conflicts);
                 continue;
             }
 
-            for &link in &graph[branch.node].links {
-                // TODO factorize repeated backtracing...
-                if link == graph.start() || Backtrace::new(graph, &accesses, branch).any(|x| x == link) {
+            let duration = branches[branch].duration;
+            for link in &graph[node].links {
+                if visited.get(usize::from(link.to)) || distances.to_end(link.to).is_none() {
+                    continue;
+                }
+                let access = &mut accesses[usize::from(link.to)];
+                *access += 1;
+                if !work_queue.accepts(*access) {
                     continue;
                 }
-                let new_branch = branch_generator.create(link);
-                accesses[usize::from(new_branch.node)].insert(new_branch.id, branch);
-                work_queue.push(new_branch, &accesses);
+                let mut visited = visited.clone();
+                visited.add(usize::from(link.to));
+                let new_branch = branches.push(Branch {
+                    node: link.to,
+                    duration: duration + link.length,
+                    parent: Some(branch),
+                    visited: Some(visited),
+                });
+                work_queue.push(new_branch, *access, duration + link.length);
             }
         };
-        Some(
-            group
-                .into_iter()
-                .map(|branch| {
-                    Backtrace::new(graph, &accesses, branch)
-                        .skip(1)
-                        .collect()
-                })
-                .collect(),
-        )
+        Some(group.into_iter().map(|branch| path(&branches, branch)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Path;
+    use crate::{Graph, SolveOptions};
+
+    #[test]
+    fn deadline_stops_the_search() {
+        use rand::SeedableRng;
+        let rng = rand::rngs::StdRng::seed_from_u64(0);
+        let graph = Graph::random(rng, 4_000, 0.001, 10);
+        let options = SolveOptions {
+            deadline: Some(std::time::Instant::now()),
+            ..SolveOptions::default()
+        };
+
+        assert!(Path::n_shortest_with(&graph, 100, &options).is_none());
+    }
+
+    #[test]
+    fn long_tunnels_delay_branches() {
+        let graph: Graph = "1\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a 5\na-e\ns-b\nb-c\nc-e"
+            .parse()
+            .unwrap();
+        let paths = Path::n_shortest(&graph, 1).unwrap();
+
+        assert_eq!(paths[0].len(), 3);
+        assert_eq!(paths[0].duration(&graph), 3);
+    }
+
+    #[test]
+    fn paths_respect_room_capacities() {
+        let graph: Graph = include_str!("../../../maps/generated/big").parse().unwrap();
+        let paths = Path::n_shortest(&graph, 10).unwrap();
+        let mut load = vec![0; graph.nodes().len()];
+        for path in &paths {
+            for &id in &path.as_ref()[..path.len() - 1] {
+                load[usize::from(id)] += 1;
+            }
+        }
+
+        assert_eq!(paths.len(), 10);
+        for (id, node) in graph.nodes().iter().enumerate() {
+            assert!(load[id] <= node.capacity);
+        }
     }
 }
 
@@ -198,6 +279,12 @@ mod benches {
         b.iter(|| Path::n_shortest(&graph, 2));
     }
 
+    #[bench]
+    fn shortest_10_paths_for_big_map(b: &mut Bencher) {
+        let graph = include_str!("../../../maps/generated/big").parse().unwrap();
+        b.iter(|| Path::n_shortest(&graph, 10));
+    }
+
     #[bench]
     fn shortest_2_paths_random(b: &mut Bencher) {
         use rand::SeedableRng;
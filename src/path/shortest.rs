@@ -1,35 +1,260 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
 use super::Path;
-use crate::Graph;
-use std::collections::VecDeque;
+use crate::{Graph, NodeId};
+
+/// Fewest turns between every room & start or end, `None` for the rooms
+/// cut off from them
+#[derive(Debug, Clone)]
+pub struct Distances {
+    from_start: Vec<Option<usize>>,
+    to_end: Vec<Option<usize>>,
+}
+
+impl Distances {
+    pub fn new(graph: &Graph) -> Self {
+        let predecessors = Predecessors::new(graph);
+        let mut forward = Frontier::new(graph.nodes().len(), graph.start());
+        let mut backward = Frontier::new(graph.nodes().len(), graph.end());
+        while forward.settle(|id| graph.links(id)).is_some() {}
+        while backward.settle(|id| predecessors.of(id)).is_some() {}
+        Self { from_start: forward.distances(), to_end: backward.distances() }
+    }
+
+    /// Turns from start to `id`
+    pub fn from_start(&self, id: NodeId) -> Option<usize> {
+        self.from_start[usize::from(id)]
+    }
+
+    /// Turns from `id` to end
+    pub fn to_end(&self, id: NodeId) -> Option<usize> {
+        self.to_end[usize::from(id)]
+    }
+}
+
+/// Dijkstra search from start or end, one room at a time
+struct Frontier {
+    /// Turns from the origin & the room it was reached from, final once
+    /// the room is settled
+    reached: Vec<Option<(usize, NodeId)>>,
+    settled: Vec<bool>,
+    queue: BinaryHeap<Reverse<(usize, NodeId)>>,
+}
+
+impl Frontier {
+    fn new(node_count: usize, origin: NodeId) -> Self {
+        let mut reached = vec![None; node_count];
+        reached[usize::from(origin)] = Some((0, origin));
+        Self {
+            reached,
+            settled: vec![false; node_count],
+            queue: BinaryHeap::from([Reverse((0, origin))]),
+        }
+    }
+
+    fn distance(&self, id: NodeId) -> Option<usize> {
+        self.reached[usize::from(id)].map(|(distance, _)| distance)
+    }
+
+    /// Least turns to the rooms not settled yet
+    fn horizon(&mut self) -> Option<usize> {
+        while let Some(&Reverse((distance, id))) = self.queue.peek() {
+            if !self.settled[usize::from(id)] {
+                return Some(distance);
+            }
+            self.queue.pop();
+        }
+        None
+    }
+
+    /// Settle the closest room & reach the rooms next to it, `links` of a
+    /// room being the (room, turns) it leads to going away from the origin
+    fn settle<I: Iterator<Item = (NodeId, usize)>>(
+        &mut self,
+        links: impl Fn(NodeId) -> I,
+    ) -> Option<NodeId> {
+        self.horizon()?;
+        let Reverse((distance, id)) = self.queue.pop()?;
+        self.settled[usize::from(id)] = true;
+        for (neighbour, length) in links(id) {
+            let next = distance + length;
+            let reached = &mut self.reached[usize::from(neighbour)];
+            if reached.is_none_or(|(distance, _)| next < distance) {
+                *reached = Some((next, id));
+                self.queue.push(Reverse((next, neighbour)));
+            }
+        }
+        Some(id)
+    }
+
+    /// Rooms from `id` back to the origin, both included
+    fn trace(&self, mut id: NodeId) -> Vec<NodeId> {
+        let mut rooms = vec![id];
+        while let Some((_, parent)) = self.reached[usize::from(id)] {
+            if parent == id {
+                break;
+            }
+            id = parent;
+            rooms.push(id);
+        }
+        rooms
+    }
+
+    /// Turns to the settled rooms
+    fn distances(&self) -> Vec<Option<usize>> {
+        self.reached
+            .iter()
+            .zip(&self.settled)
+            .map(|(reached, &settled)| reached.filter(|_| settled).map(|(distance, _)| distance))
+            .collect()
+    }
+}
+
+/// Rooms with a link to each room & its length, flattened: the ones of
+/// room `i` are `rooms[offsets[i]..offsets[i + 1]]`
+struct Predecessors {
+    offsets: Vec<usize>,
+    rooms: Vec<(NodeId, usize)>,
+}
+
+impl Predecessors {
+    fn new(graph: &Graph) -> Self {
+        let mut offsets = vec![0; graph.nodes().len() + 1];
+        for link in graph.nodes().iter().flat_map(|node| &node.links) {
+            offsets[usize::from(link.to) + 1] += 1;
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+        let mut filled = offsets.clone();
+        let mut rooms = vec![(graph.start(), 0); offsets[graph.nodes().len()]];
+        for (id, node) in graph.nodes().iter().enumerate() {
+            for link in &node.links {
+                rooms[filled[usize::from(link.to)]] = (NodeId::from(id), link.length);
+                filled[usize::from(link.to)] += 1;
+            }
+        }
+        Self { offsets, rooms }
+    }
+
+    /// Rooms leading to `id` & the turns it takes
+    fn of(&self, id: NodeId) -> impl Iterator<Item = (NodeId, usize)> + '_ {
+        self.rooms[self.offsets[usize::from(id)]..self.offsets[usize::from(id) + 1]].iter().copied()
+    }
+}
+
+impl Graph {
+    /// Rooms `id` leads to & the turns it takes
+    fn links(&self, id: NodeId) -> impl Iterator<Item = (NodeId, usize)> + '_ {
+        self[id].links.iter().map(|link| (link.to, link.length))
+    }
+}
 
 impl Path {
+    /// Path taking the fewest turns, searched from start & end at once
     pub fn shortest(graph: &Graph) -> Option<Self> {
-        let mut accesses = vec![None; graph.nodes().len()];
-        let mut active_nodes = VecDeque::new();
-
-        let mut id = graph.start();
-        accesses[usize::from(id)] = Some(id);
-        loop {
-            if id == graph.end() {
+        let node_count = graph.nodes().len();
+        let predecessors = Predecessors::new(graph);
+        let mut forward = Frontier::new(node_count, graph.start());
+        let mut backward = Frontier::new(node_count, graph.end());
+        // Turns of the quickest way found yet from both sides & the tunnel
+        // joining them
+        let mut meeting = (graph.start() == graph.end()).then_some((0, graph.start(), graph.end()));
+        while let (Some(ahead), Some(behind)) = (forward.horizon(), backward.horizon()) {
+            // No way through the rooms left can be quicker
+            if meeting.is_some_and(|(turns, ..)| ahead + behind >= turns) {
                 break;
             }
-            for link in &graph[id].links {
-                let access = &mut accesses[usize::from(id)];
-                if access.is_none() {
-                    *access = Some(id);
-                    active_nodes.push_back(link);
+            // Searching the smaller side keeps both searches small, every
+            // tunnel between the sides joins them
+            let mut joins = Vec::new();
+            if forward.queue.len() <= backward.queue.len() {
+                let Some(from) = forward.settle(|id| graph.links(id)) else { break };
+                for (to, length) in graph.links(from) {
+                    joins.extend(backward.distance(to).map(|rest| (from, length, to, rest)));
+                }
+            } else {
+                let Some(to) = backward.settle(|id| predecessors.of(id)) else { break };
+                for (from, length) in predecessors.of(to) {
+                    joins.extend(backward.distance(to).map(|rest| (from, length, to, rest)));
+                }
+            }
+            for (from, length, to, rest) in joins {
+                let Some(turns) = forward.distance(from).map(|turns| turns + length + rest) else {
+                    continue;
+                };
+                if from != to && meeting.is_none_or(|(best, ..)| turns < best) {
+                    meeting = Some((turns, from, to));
                 }
             }
-            id = *active_nodes.pop_front()?;
         }
 
-        let mut path = vec![id];
-        while id != graph.start() {
-            id = accesses[usize::from(id)].unwrap();
-            path.push(id);
+        meeting.map(|(_, from, to)| {
+            let mut rooms = forward.trace(from);
+            rooms.reverse();
+            if from != to {
+                rooms.extend(backward.trace(to));
+            }
+            rooms.into_iter().skip(1).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Distances, Path};
+    use crate::Graph;
+
+    #[test]
+    fn shortest_path_follows_links() {
+        let graph: Graph = "1\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a\na-b\nb-e\ns-c\nc>e"
+            .parse()
+            .unwrap();
+        let id = |name| graph.find(name).unwrap();
+        let path = Path::shortest(&graph).unwrap();
+        let distances = Distances::new(&graph);
+
+        assert_eq!(path.as_ref(), [id("c"), id("e")]);
+        assert_eq!(distances.from_start(id("b")), Some(2));
+        assert_eq!(distances.to_end(id("b")), Some(1));
+        // Directed the other way
+        assert_eq!(distances.to_end(id("c")), Some(1));
+        assert_eq!(distances.from_start(id("e")), Some(2));
+
+        let reversed: Graph = "1\n##start\ns 0 0\nc 0 0\n##end\ne 0 0\ne>c\nc>s".parse().unwrap();
+        assert!(Path::shortest(&reversed).is_none());
+    }
+
+    #[test]
+    fn long_tunnels_count_as_many_turns() {
+        let graph: Graph = "1\n##start\ns 0 0\na 0 0\nb 0 0\nc 0 0\n##end\ne 0 0\n\
+            s-a 5\na>e\ns-b\nb-c\nc>e"
+            .parse()
+            .unwrap();
+        let id = |name| graph.find(name).unwrap();
+        let distances = Distances::new(&graph);
+
+        assert_eq!(Path::shortest(&graph).unwrap().as_ref(), [id("b"), id("c"), id("e")]);
+        assert_eq!(distances.from_start(id("a")), Some(5));
+        assert_eq!(distances.to_end(id("a")), Some(1));
+    }
+
+    #[test]
+    fn shortest_path_length_matches_a_plain_search() {
+        use rand::SeedableRng;
+        let rng = rand::rngs::StdRng::seed_from_u64(0);
+        let graph = Graph::random(rng, 1_000, 0.003, 10);
+        let path = Path::shortest(&graph).unwrap();
+        let distances = Distances::new(&graph);
+
+        // Every tunnel takes one turn
+        assert_eq!(Some(path.duration(&graph)), graph.shortest_duration());
+        assert_eq!(path.as_ref().last(), Some(&graph.end()));
+        for &id in path.as_ref() {
+            let through = distances.from_start(id).zip(distances.to_end(id));
+            assert_eq!(through.map(|(from, to)| from + to), Some(path.len()));
         }
-        path.reverse();
-        Some(Path(path))
     }
 }
 